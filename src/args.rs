@@ -2,12 +2,16 @@ use std::convert::Infallible;
 use std::path::PathBuf;
 use std::str::FromStr;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use clap_complete::Shell;
 use log::LevelFilter;
 
-const LOG_VALUES: [&str; 6] = ["Off", "Error", "Warn", "Info", "Debug", "Trace"];
-const COLOR_MODES: [&str; 3] = ["Auto", "Always", "Never"];
-const SORTING_MODES: [&str; 4] = ["Name", "Title", "Slug", "Id"];
+use crate::graph::GraphFormat;
+use crate::object::Side;
+
+pub const LOG_VALUES: [&str; 6] = ["Off", "Error", "Warn", "Info", "Debug", "Trace"];
+pub const COLOR_MODES: [&str; 3] = ["Auto", "Always", "Never"];
+pub const SORTING_MODES: [&str; 4] = ["Name", "Title", "Slug", "Id"];
 
 macro_rules! possible_values_parser {
   ($t:ty: $values:expr) => {{
@@ -17,51 +21,107 @@ macro_rules! possible_values_parser {
   }};
 }
 
-#[derive(Parser, Debug)]
+/// Options are global so they work both before and after a subcommand,
+/// running without a subcommand is the same as running `generate`
+#[derive(Parser, Debug, Clone)]
 #[clap(author, version, about, long_about = None)]
 pub struct Args {
+  #[clap(subcommand)]
+  pub command: Option<Command>,
   /// Path to the packwiz directory containing 'pack.toml'
-  #[clap(long, short = 'p', default_value = "./", value_hint = clap::ValueHint::DirPath)]
+  #[clap(long, short = 'p', default_value = "./", value_hint = clap::ValueHint::DirPath, global = true)]
   pub path: PathBuf,
+  /// Set the config file [default: 'packwizml.toml' in '--path',
+  /// or the '[options.packwiz-modlist]' table in 'pack.toml']
+  #[clap(long, value_hint = clap::ValueHint::FilePath, global = true)]
+  pub config: Option<PathBuf>,
   /// Set the cache file
-  #[clap(long, default_value = "./.packwiz-modlist.cache.json")]
+  #[clap(long, default_value = "./.packwiz-modlist.cache.json", global = true)]
   pub cache: PathBuf,
+  /// Set a project cache file that can be shared between multiple packs
+  #[clap(long, value_hint = clap::ValueHint::FilePath, global = true)]
+  pub shared_cache: Option<PathBuf>,
   /// Path to the directory contains all the mod metadata files
-  #[clap(long, short = 'm', default_value = "mods", value_hint = clap::ValueHint::DirPath)]
+  #[clap(long, short = 'm', default_value = "mods", value_hint = clap::ValueHint::DirPath, global = true)]
   pub mods: PathBuf,
   /// Disable '--mods' being relative to '--path'
-  #[clap(short = 'M')]
+  #[clap(short = 'M', global = true)]
   pub mods_custom: bool,
   /// Set an output file
-  #[clap(long, short = 'o')]
+  #[clap(long, short = 'o', global = true)]
   pub output: Option<PathBuf>,
   /// Disable'`--output' being relative to '--path'
-  #[clap(short = 'O')]
+  #[clap(short = 'O', global = true)]
   pub output_custom: bool,
+  /// Only replaces what's between '<!-- packwizml:start -->' and '<!-- packwizml:end -->'
+  /// in an existing '--output', leaving the rest of the file untouched
+  #[clap(long, short = 'i', global = true)]
+  pub inject: bool,
   /// Overwrites output if it already exists
-  #[clap(long, short = 'F')]
+  #[clap(long, short = 'F', global = true)]
   pub force: bool,
+  /// Contact info sent in the User-Agent, defaults to the repository URL
+  #[clap(long, global = true)]
+  pub contact: Option<String>,
+  /// Timeout for each request in seconds
+  #[clap(long, default_value = "30", global = true)]
+  pub timeout: u64,
+  /// Path to an extra PEM encoded CA certificate to trust, e.g. for a corporate proxy
+  #[clap(long, value_hint = clap::ValueHint::FilePath, global = true)]
+  pub ca_cert: Option<PathBuf>,
+  /// CurseForge API key, CurseForge projects are only fetched when one is found
+  #[clap(long, env = "CF_API_KEY", hide_env_values = true, global = true)]
+  pub curseforge_api_key: Option<String>,
+  /// Base URL of the CurseForge API [default: https://api.curseforge.com/v1]
+  #[clap(long, env = "PACKWIZML_CURSEFORGE_API", value_hint = clap::ValueHint::Url, global = true)]
+  pub curseforge_api: Option<String>,
+  /// Base URL of the Modrinth API [default: https://api.modrinth.com/v2]
+  #[clap(long, env = "PACKWIZML_MODRINTH_API", value_hint = clap::ValueHint::Url, global = true)]
+  pub modrinth_api: Option<String>,
+  /// Max amount of times a failed or rate limited request is retried
+  #[clap(long, default_value = "3", global = true)]
+  pub max_retries: u32,
   /// Sets the verbosity of logging
   #[clap(
-    long, short = 'v', ignore_case = true, default_value = "Warn",
+    long, short = 'v', global = true, ignore_case = true, default_value = "Warn",
     value_parser = possible_values_parser!(LevelFilter: LOG_VALUES)
   )]
   pub log_level: LevelFilter,
   /// Sets the color mode
   #[clap(
-    long, short = 'c', ignore_case = true, default_value = "Auto", 
+    long, short = 'c', global = true, ignore_case = true, default_value = "Auto", 
     value_parser = possible_values_parser!(ColorMode: COLOR_MODES)
   )]
   pub color_mode: ColorMode,
   /// Sets the sorting mode
   #[clap(
-    long, short = 's', ignore_case = true,
+    long, short = 's', global = true, ignore_case = true,
     value_parser = possible_values_parser!(SortingMode: SORTING_MODES)
   )]
   pub sort_by: Option<SortingMode>,
   /// Sets if sorting should be reverse
-  #[clap(long, short = 'r')]
+  #[clap(long, short = 'r', global = true)]
   pub reverse: bool,
+  /// Skips metafiles that fail to parse instead of stopping, with a summary at the end
+  #[clap(long, global = true)]
+  pub keep_going: bool,
+  /// Looks up the dependencies of each mod's version, for '{DEPENDENCIES}', '{REQUIRED_BY}',
+  /// listing libraries last and warning about missing dependencies
+  #[clap(long, global = true)]
+  pub dependencies: bool,
+  /// Fails when a platform doesn't return a project, instead of warning
+  #[clap(long, global = true)]
+  pub strict: bool,
+  /// Lists projects a platform didn't return using data from their metafile
+  #[clap(long, global = true, conflicts_with = "strict")]
+  pub metafile_fallback: bool,
+  /// Checks that outputs are up to date instead of writing them,
+  /// prints a diff and exits with 1 if any are outdated
+  #[clap(long, global = true, conflicts_with = "watch")]
+  pub check: bool,
+  /// Regenerates whenever 'pack.toml', 'index.toml' or the mods directory changes
+  #[clap(long, short = 'w', global = true)]
+  pub watch: bool,
   /// Prints about this program
   #[clap(long, global = true)]
   pub about: bool,
@@ -72,12 +132,86 @@ pub struct Args {
   #[clap(
     long,
     short = 'f',
+    global = true,
     allow_hyphen_values = true,
     default_value = "- [{NAME}]({URL}) - {DESCRIPTION}\n"
   )]
   pub format: String,
 }
 
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+  /// Generates the modlist, this is the default
+  Generate,
+  /// Same as 'generate --check'
+  Check,
+  /// Checks 'pack.toml' and the metafiles against packwiz's spec
+  Validate,
+  /// Checks the jars in a directory, e.g. a server's mods folder, against the metafile hashes
+  Verify {
+    /// Directory with the downloaded jars
+    #[clap(value_hint = clap::ValueHint::DirPath)]
+    dir: PathBuf,
+    /// Only expect mods for this side
+    #[clap(long, value_enum)]
+    side: Option<Side>,
+  },
+  /// Compares an installed instance or server mods folder with the pack
+  Compare {
+    /// Directory with the installed jars
+    #[clap(value_hint = clap::ValueHint::DirPath)]
+    dir: PathBuf,
+    /// Only expect mods for this side
+    #[clap(long, value_enum)]
+    side: Option<Side>,
+  },
+  /// Groups the mods by license and flags those that don't allow third-party distribution
+  Licenses {
+    /// Only include mods for this side, e.g. 'server' before publishing a server pack
+    #[clap(long, value_enum)]
+    side: Option<Side>,
+  },
+  /// Prints the mod dependency graph, '--format' picks 'dot' (the default) or 'mermaid'
+  Graph {
+    /// Set from '--format' by [GraphFormat::from_matches], since that's a global option
+    #[clap(skip)]
+    format: GraphFormat,
+  },
+  /// Manage the config file
+  Config {
+    #[clap(subcommand)]
+    command: ConfigCommand,
+  },
+  /// Manage the cache
+  Cache {
+    #[clap(subcommand)]
+    command: CacheCommand,
+  },
+  /// Creates a 'packwizml.toml' in '--path' with every option
+  Init,
+  /// Prints shell completions
+  Completions {
+    #[clap(value_enum)]
+    shell: Shell,
+  },
+  /// Prints the man page in roff format
+  Man,
+}
+
+#[derive(Subcommand, Debug, Copy, Clone)]
+pub enum ConfigCommand {
+  /// Prints the effective settings, after merging the config file and command line
+  Show,
+}
+
+#[derive(Subcommand, Debug, Copy, Clone)]
+pub enum CacheCommand {
+  /// Prints where the cache files are
+  Path,
+  /// Deletes the cache file, the shared cache is left alone
+  Clear,
+}
+
 #[derive(Debug, Copy, Clone)]
 pub enum ColorMode {
   Auto,
@@ -1,9 +1,14 @@
 use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use serde::{Deserialize, Serialize};
 use tokio::fs::OpenOptions;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::AsyncWriteExt;
 
+use crate::error::GlobalError;
 use crate::GlobalError::Validation;
 use crate::ValidationError::MustBeFile;
 use crate::{
@@ -12,11 +17,21 @@ use crate::{
   object::{PackMod, Project},
 };
 
-#[derive(Default, Debug, Clone, Serialize, Deserialize)]
-pub struct Cache(HashMap<String, CacheProject>);
+/// Bumped whenever cached projects gain fields,
+/// entries written by other versions are dropped so they get fetched again
+const CACHE_VERSION: u32 = 1;
+
+#[derive(Default, Debug, Clone)]
+pub struct Cache {
+  projects: HashMap<String, CacheProject>,
+  shared: Option<SharedCache>,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct CacheProject {
+  /// Missing in caches from before it was versioned
+  #[serde(default)]
+  version: u32,
   hash: String,
   project: Project,
 }
@@ -25,50 +40,74 @@ impl Cache {
   pub async fn new(args: &Args) -> GlobalResult<Self> {
     let path = args.cache.clone();
 
-    let mut file = match () {
+    let bytes = match () {
       _ if path.is_dir() => return Err(Validation(MustBeFile(path))),
-      _ if !path.exists() => {
-        let mut file = OpenOptions::new()
-          .write(true)
-          .create(true)
-          .open(&args.cache)
-          .await?;
-
-        file.write_all(b"{}").await?;
-        file.flush().await?;
-        file.sync_all().await?;
-
-        OpenOptions::new().read(true).open(&args.cache).await?
-      }
-      _ => OpenOptions::new().read(true).open(&args.cache).await?,
+      // Created by the first save, so commands that don't save leave nothing behind
+      _ if !path.exists() => b"{}".to_vec(),
+      _ => tokio::fs::read(&path).await?,
     };
 
-    let size = file.metadata().await?.len() as _;
-    let mut bytes = Vec::with_capacity(size);
+    let mut hashmap: HashMap<String, CacheProject> = serde_json::from_slice(&bytes)?;
 
-    file.read_to_end(&mut bytes).await?;
+    hashmap.retain(|_, it| it.version == CACHE_VERSION);
 
-    let hashmap = serde_json::from_slice(&bytes)?;
+    let shared = match &args.shared_cache {
+      Some(path) => Some(SharedCache::load(path).await?),
+      None => None,
+    };
 
-    Ok(Self(hashmap))
+    Ok(Self {
+      projects: hashmap,
+      shared,
+    })
   }
 
   pub async fn save(&self, args: &Args) -> GlobalResult<()> {
-    let mut file = OpenOptions::new().write(true).open(&args.cache).await?;
-    let bytes = serde_json::to_vec_pretty(self)?;
+    let mut file = OpenOptions::new()
+      .write(true)
+      .create(true)
+      .truncate(true)
+      .open(&args.cache)
+      .await?;
+    let bytes = serde_json::to_vec_pretty(&self.projects)?;
 
     file.write_all(&bytes).await?;
     file.flush().await?;
 
+    if let (Some(shared), Some(path)) = (&self.shared, &args.shared_cache) {
+      shared.clone().save(path).await?;
+    }
+
     Ok(())
   }
 
+  /// Deletes the cache file if it exists, returns if anything was deleted
+  pub async fn clear(args: &Args) -> GlobalResult<bool> {
+    match () {
+      _ if args.cache.is_dir() => Err(Validation(MustBeFile(args.cache.clone()))),
+      _ if !args.cache.exists() => Ok(false),
+      _ => {
+        tokio::fs::remove_file(&args.cache).await?;
+        Ok(true)
+      }
+    }
+  }
+
   pub fn insert(&mut self, pack_mod: &PackMod, project: Project) {
-    let id = pack_mod.id();
+    let key = project.key();
     let hash = pack_mod.hash().clone();
-    let value = CacheProject { hash, project };
 
-    self.0.insert(id, value);
+    if let Some(shared) = &mut self.shared {
+      shared.insert(project.clone());
+    }
+
+    let value = CacheProject {
+      version: CACHE_VERSION,
+      hash,
+      project,
+    };
+
+    self.projects.insert(key, value);
   }
 
   pub fn insert_all<'a, I>(&mut self, mods: I)
@@ -78,20 +117,171 @@ impl Cache {
     mods.into_iter().for_each(|it| self.insert(it.0, it.1));
   }
 
-  pub fn get_all<'a, I>(&self, mods: I) -> Option<Vec<&Project>>
+  /// Splits mods into projects found in the cache and mods that still need to be fetched
+  pub fn split<'a, I>(&self, mods: I) -> (Vec<Project>, Vec<&'a PackMod>)
   where
     I: IntoIterator<Item = &'a PackMod>,
   {
-    mods.into_iter().map(|it| self.get(it)).collect()
+    let mut cached = Vec::new();
+    let mut missing = Vec::new();
+
+    for pack_mod in mods {
+      match self.get(pack_mod) {
+        Some(project) => cached.push(project.clone()),
+        None => missing.push(pack_mod),
+      }
+    }
+
+    (cached, missing)
   }
 
   pub fn get(&self, pack_mod: &PackMod) -> Option<&Project> {
-    let CacheProject { hash, project } = self.0.get(&pack_mod.id())?;
+    let cached = self
+      .projects
+      .get(&pack_mod.key()?)
+      .filter(|it| it.hash == *pack_mod.hash() && Some(it.project.source()) == pack_mod.source());
+
+    match cached {
+      Some(CacheProject { project, .. }) => Some(project),
+      None => self.shared.as_ref()?.get(pack_mod),
+    }
+  }
+}
+
+/// Project cache that can be shared between multiple packs,
+/// keyed by source and project id instead of the metafile hash
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct SharedCache(HashMap<String, SharedCacheProject>);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SharedCacheProject {
+  /// See [CACHE_VERSION]
+  #[serde(default)]
+  version: u32,
+  /// Unix timestamp of when the project was fetched, the newest one wins when merging
+  fetched: u64,
+  project: Project,
+}
+
+fn now() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|it| it.as_secs())
+    .unwrap_or_default()
+}
+
+fn read_locked(file: &mut File) -> GlobalResult<SharedCache> {
+  let mut bytes = Vec::new();
+
+  file.read_to_end(&mut bytes)?;
+
+  if bytes.iter().all(u8::is_ascii_whitespace) {
+    return Ok(SharedCache::default());
+  }
+
+  Ok(serde_json::from_slice(&bytes)?)
+}
 
-    if hash == pack_mod.hash() {
-      Some(project)
-    } else {
-      None
+/// File locks and std file IO block, so they're kept off the async runtime
+async fn blocking<T, F>(f: F) -> GlobalResult<T>
+where
+  T: Send + 'static,
+  F: FnOnce() -> GlobalResult<T> + Send + 'static,
+{
+  tokio::task::spawn_blocking(f)
+    .await
+    .map_err(|err| GlobalError::Unknown(Box::new(err)))?
+}
+
+impl SharedCache {
+  pub async fn load(path: &Path) -> GlobalResult<Self> {
+    let path = path.to_path_buf();
+
+    blocking(move || Self::load_blocking(&path)).await
+  }
+
+  fn load_blocking(path: &Path) -> GlobalResult<Self> {
+    if path.is_dir() {
+      return Err(Validation(MustBeFile(path.to_path_buf())));
+    }
+
+    if !path.exists() {
+      return Ok(Self::default());
+    }
+
+    let mut file = File::open(path)?;
+
+    file.lock_shared()?;
+
+    let mut cache = read_locked(&mut file)?;
+
+    file.unlock()?;
+
+    cache.0.retain(|_, it| it.version == CACHE_VERSION);
+
+    Ok(cache)
+  }
+
+  /// Merges this cache into the file at `path`,
+  /// holding an exclusive lock so parallel runs don't overwrite each other
+  pub async fn save(self, path: &Path) -> GlobalResult<()> {
+    let path = path.to_path_buf();
+
+    blocking(move || self.save_blocking(&path)).await
+  }
+
+  fn save_blocking(&self, path: &Path) -> GlobalResult<()> {
+    if let Some(parent) = path.parent().filter(|it| !it.as_os_str().is_empty()) {
+      std::fs::create_dir_all(parent)?;
     }
+
+    let mut file = File::options()
+      .read(true)
+      .write(true)
+      .create(true)
+      .truncate(false)
+      .open(path)?;
+
+    file.lock()?;
+
+    let mut cache = read_locked(&mut file)?;
+
+    // Entries from newer versions are left for them
+    cache.0.retain(|_, it| it.version >= CACHE_VERSION);
+
+    for (key, value) in &self.0 {
+      match cache.0.get(key) {
+        Some(it) if it.version == CACHE_VERSION && it.fetched >= value.fetched => {}
+        _ => {
+          cache.0.insert(key.clone(), value.clone());
+        }
+      }
+    }
+
+    let bytes = serde_json::to_vec_pretty(&cache)?;
+
+    file.set_len(0)?;
+    file.rewind()?;
+    file.write_all(&bytes)?;
+    file.flush()?;
+    file.unlock()?;
+
+    Ok(())
+  }
+
+  pub fn insert(&mut self, project: Project) {
+    let value = SharedCacheProject {
+      version: CACHE_VERSION,
+      fetched: now(),
+      project,
+    };
+
+    self.0.insert(value.project.key(), value);
+  }
+
+  /// Entries are kept until the file is deleted, the metafile hash isn't checked
+  /// since the same project is pinned to different versions across packs
+  pub fn get(&self, pack_mod: &PackMod) -> Option<&Project> {
+    self.0.get(&pack_mod.key()?).map(|it| &it.project)
   }
 }
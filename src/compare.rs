@@ -0,0 +1,254 @@
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+
+use colored::Colorize;
+use itertools::Itertools;
+use log::debug;
+use serde::Deserialize;
+
+use crate::data::get_mods;
+use crate::error::{GlobalResult, ValidationError};
+use crate::object::{HashFormat, PackMod, Side};
+use crate::verify::{hash, is_for_side};
+use crate::Args;
+
+/// Mod id and version read from the metadata inside a jar
+#[derive(Debug, Clone)]
+pub struct JarInfo {
+  pub id: String,
+  pub version: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct FabricModJson {
+  id: String,
+  version: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct QuiltModJson {
+  quilt_loader: FabricModJson,
+}
+
+#[derive(Deserialize)]
+struct ForgeModsToml {
+  #[serde(default)]
+  mods: Vec<ForgeMod>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ForgeMod {
+  mod_id: String,
+  version: Option<String>,
+}
+
+fn read_entry(archive: &mut zip::ZipArchive<std::fs::File>, name: &str) -> Option<String> {
+  let mut file = archive.by_name(name).ok()?;
+  let mut content = String::new();
+
+  file.read_to_string(&mut content).ok()?;
+
+  Some(content)
+}
+
+/// Reads the mod id from `fabric.mod.json`, `quilt.mod.json`,
+/// `META-INF/mods.toml` or `META-INF/neoforge.mods.toml`
+pub fn jar_info(path: &Path) -> Option<JarInfo> {
+  let file = std::fs::File::open(path).ok()?;
+  let mut archive = match zip::ZipArchive::new(file) {
+    Ok(archive) => archive,
+    Err(err) => {
+      debug!("Could not open {} as a jar: {err}", path.display());
+      return None;
+    }
+  };
+
+  let fabric = read_entry(&mut archive, "fabric.mod.json")
+    .and_then(|it| serde_json::from_str::<FabricModJson>(&it).ok());
+
+  let quilt = || {
+    read_entry(&mut archive, "quilt.mod.json")
+      .and_then(|it| serde_json::from_str::<QuiltModJson>(&it).ok())
+      .map(|it| it.quilt_loader)
+  };
+
+  if let Some(FabricModJson { id, version }) = fabric.or_else(quilt) {
+    return Some(JarInfo { id, version });
+  }
+
+  let forge = ["META-INF/neoforge.mods.toml", "META-INF/mods.toml"]
+    .into_iter()
+    .find_map(|name| read_entry(&mut archive, name))
+    .and_then(|it| toml::from_str::<ForgeModsToml>(&it).ok())?
+    .mods
+    .into_iter()
+    .next()?;
+
+  Some(JarInfo {
+    id: forge.mod_id,
+    // Usually filled in from the manifest at build time, which isn't worth reading
+    version: forge.version.filter(|it| !it.starts_with("${")),
+  })
+}
+
+fn normalize(it: &str) -> String {
+  it.chars()
+    .filter(|it| it.is_ascii_alphanumeric())
+    .collect::<String>()
+    .to_lowercase()
+}
+
+/// Metafiles don't know mod ids, so they're guessed from the name and the jar's filename
+fn is_same_mod(pack_mod: &PackMod, info: &JarInfo) -> bool {
+  let id = normalize(&info.id);
+
+  !id.is_empty()
+    && (normalize(&pack_mod.name) == id
+      || pack_mod
+        .filename
+        .split(['-', '_', '+'])
+        .next()
+        .is_some_and(|it| normalize(it) == id))
+}
+
+/// A pack mod that's installed, but not the same file as in the pack
+#[derive(Debug)]
+pub struct Different {
+  pub name: String,
+  pub expected: String,
+  pub installed: String,
+  pub version: Option<String>,
+}
+
+/// Result of comparing an installed mods folder with the pack
+#[derive(Debug, Default)]
+pub struct Report {
+  pub matched: usize,
+  /// Pack mods that aren't installed
+  pub missing: Vec<String>,
+  /// Installed jars that aren't in the pack
+  pub extra: Vec<String>,
+  pub different: Vec<Different>,
+}
+
+impl Report {
+  pub fn is_ok(&self) -> bool {
+    self.missing.is_empty() && self.extra.is_empty() && self.different.is_empty()
+  }
+
+  pub fn print(&self) {
+    for name in &self.missing {
+      println!("{} {name}", "missing  ".bright_red());
+    }
+
+    for Different {
+      name,
+      expected,
+      installed,
+      version,
+    } in &self.different
+    {
+      let version = version
+        .as_ref()
+        .map(|it| format!(" ({it})"))
+        .unwrap_or_default();
+
+      println!(
+        "{} {name}, pack has {expected}, installed {installed}{version}",
+        "different".bright_yellow()
+      );
+    }
+
+    for filename in &self.extra {
+      println!("{} {filename}", "extra    ".bright_yellow());
+    }
+  }
+}
+
+/// Matches the jars in `dir` to the pack's mods, first by hash, then by the mod id in the jar
+pub fn compare(args: &Args, dir: &Path, side: Option<Side>) -> GlobalResult<Report> {
+  match () {
+    _ if !dir.exists() => return Err(ValidationError::DirNotExist(dir.to_path_buf()).into()),
+    _ if !dir.is_dir() => return Err(ValidationError::MustBeDir(dir.to_path_buf()).into()),
+    _ => {}
+  }
+
+  let (mods, _) = get_mods(args)?;
+  let mut unmatched = mods
+    .iter()
+    .filter(|it| is_for_side(side, it.side))
+    .collect::<Vec<_>>();
+
+  let mut jars = dir
+    .read_dir()?
+    .filter_map(|it| it.ok())
+    .filter(|it| it.file_type().is_ok_and(|it| it.is_file()))
+    .map(|it| it.path())
+    .collect::<Vec<_>>();
+
+  jars.sort();
+
+  let formats = unmatched
+    .iter()
+    .map(|it| it.download.hash_format)
+    .unique()
+    .collect::<Vec<_>>();
+
+  let mut report = Report::default();
+  let mut unknown = Vec::new();
+
+  for path in jars {
+    let data = std::fs::read(&path)?;
+    let hashes = formats
+      .iter()
+      .map(|it| (*it, hash(*it, &data)))
+      .collect::<HashMap<HashFormat, String>>();
+
+    let found = unmatched.iter().position(|it| {
+      hashes
+        .get(&it.download.hash_format)
+        .is_some_and(|hash| hash.eq_ignore_ascii_case(it.hash()))
+    });
+
+    match found {
+      Some(index) => {
+        unmatched.remove(index);
+        report.matched += 1;
+      }
+      None => unknown.push(path),
+    }
+  }
+
+  for path in unknown {
+    let filename = path
+      .file_name()
+      .unwrap_or_default()
+      .to_string_lossy()
+      .into_owned();
+    let info = jar_info(&path);
+    let found = info
+      .as_ref()
+      .and_then(|info| unmatched.iter().position(|it| is_same_mod(it, info)));
+
+    match found {
+      Some(index) => {
+        let pack_mod = unmatched.remove(index);
+
+        report.different.push(Different {
+          name: pack_mod.name.clone(),
+          expected: pack_mod.filename.clone(),
+          installed: filename,
+          version: info.and_then(|it| it.version),
+        });
+      }
+      None => report.extra.push(filename),
+    }
+  }
+
+  report.missing = unmatched.iter().map(|it| it.name.clone()).collect();
+  report.missing.sort();
+
+  Ok(report)
+}
@@ -0,0 +1,427 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use clap::parser::ValueSource;
+use clap::ArgMatches;
+use log::{debug, LevelFilter};
+use serde::{Deserialize, Serialize};
+
+use crate::args::{Args, ColorMode, SortingMode, COLOR_MODES, LOG_VALUES, SORTING_MODES};
+use crate::data::read_toml_file;
+use crate::error::{GlobalResult, ValidationError};
+use crate::output::{Output, OutputFilter, Preset, Render};
+
+/// Name of the config file looked for in '--path'
+pub const CONFIG_FILE: &str = "packwizml.toml";
+/// Table in `pack.toml`'s `[options]` used when there's no config file
+pub const PACK_OPTIONS_TABLE: &str = "packwiz-modlist";
+
+/// Service and user the CurseForge API key is stored under in the OS keyring
+pub const KEYRING_SERVICE: &str = "packwiz-modlist";
+pub const KEYRING_USER: &str = "curseforge-api-key";
+
+/// Written by `packwizml init`, every option is commented out with its default
+const CONFIG_TEMPLATE: &str = r#"# packwiz-modlist config, options given on the command line take priority
+# https://github.com/Ricky12Awesome/packwiz-modlist
+
+# cache = "./.packwiz-modlist.cache.json"
+# shared-cache = "~/.cache/packwizml.shared.json"
+# mods = "mods"
+# mods-custom = false
+# output = "MODLIST.md"
+# output-custom = false
+# inject = false
+# force = false
+# contact = "you@example.com"
+# timeout = 30
+# ca-cert = "proxy-ca.pem"
+# curseforge-api = "https://api.curseforge.com/v1"
+# modrinth-api = "https://api.modrinth.com/v2"
+# max-retries = 3
+# log-level = "Warn"
+# color-mode = "Auto"
+# sort-by = "Name"
+# reverse = false
+# keep-going = false
+# dependencies = false
+# strict = false
+# metafile-fallback = false
+# json = false
+# format = "- [{NAME}]({URL}) - {DESCRIPTION}\n"
+
+# Outputs that all get written in a single run
+# [outputs.modlist]
+# path = "MODLIST.md"
+# preset = "markdown"
+# inject = false
+# filter = { sides = ["client", "both"], sources = ["modrinth", "curseforge"], exclude = [], libraries = false }
+"#;
+
+/// Key baked in at build time, only used when no other key was found
+const BUILTIN_CURSEFORGE_API_KEY: Option<&str> = option_env!("CF_API_KEY");
+
+/// Per-user settings that shouldn't live inside a pack, like API keys
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct UserConfig {
+  pub curseforge_api_key: Option<String>,
+  pub curseforge_api: Option<String>,
+  pub modrinth_api: Option<String>,
+}
+
+impl UserConfig {
+  /// `<config dir>/packwiz-modlist/config.toml`
+  pub fn path() -> Option<PathBuf> {
+    dirs::config_dir().map(|it| it.join("packwiz-modlist").join("config.toml"))
+  }
+
+  pub fn load() -> GlobalResult<Self> {
+    match Self::path() {
+      Some(path) if path.is_file() => read_toml_file(path),
+      _ => Ok(Self::default()),
+    }
+  }
+}
+
+fn keyring_curseforge_api_key() -> Option<String> {
+  let entry = keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER);
+
+  match entry.and_then(|it| it.get_password()) {
+    Ok(key) => Some(key),
+    Err(keyring::Error::NoEntry) => None,
+    Err(err) => {
+      debug!("Could not read CurseForge API key from keyring: {err}");
+      None
+    }
+  }
+}
+
+/// Where [curseforge_api_key] looks, for messages that tell the user how to set one
+pub const CURSEFORGE_API_KEY_SOURCES: &str = "'--curseforge-api-key', \
+  the CF_API_KEY environment variable, the user config or the OS keyring";
+
+/// Only looked up once, the first time a CurseForge project has to be fetched
+static STORED_CURSEFORGE_API_KEY: OnceLock<Option<String>> = OnceLock::new();
+
+/// Finds the CurseForge API key, checking in order
+/// `--curseforge-api-key`/`CF_API_KEY` and the user config (applied by [resolve]),
+/// then the OS keyring and the key the binary was built with, an empty key turns CurseForge off
+pub fn curseforge_api_key(args: &Args) -> Option<String> {
+  let key = match &args.curseforge_api_key {
+    Some(key) => Some(key.clone()),
+    None => STORED_CURSEFORGE_API_KEY
+      .get_or_init(|| {
+        keyring_curseforge_api_key().or_else(|| BUILTIN_CURSEFORGE_API_KEY.map(String::from))
+      })
+      .clone(),
+  };
+
+  key.filter(|it| !it.trim().is_empty())
+}
+
+/// Applies settings that are resolved at runtime onto `args`,
+/// the keyring is left for [curseforge_api_key] so commands that don't need it never touch it
+pub fn resolve(args: &mut Args) -> GlobalResult<()> {
+  let config = UserConfig::load()?;
+
+  if args.curseforge_api_key.is_none() {
+    args.curseforge_api_key = config.curseforge_api_key;
+  }
+
+  if args.curseforge_api.is_none() {
+    args.curseforge_api = config.curseforge_api;
+  }
+
+  if args.modrinth_api.is_none() {
+    args.modrinth_api = config.modrinth_api;
+  }
+
+  Ok(())
+}
+
+/// Per-pack defaults for every option in [Args], overridden by the command line.
+///
+/// Read from `packwizml.toml`, or the `[options.packwiz-modlist]` table in `pack.toml`
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ProjectConfig {
+  pub cache: Option<PathBuf>,
+  pub shared_cache: Option<PathBuf>,
+  pub mods: Option<PathBuf>,
+  pub mods_custom: Option<bool>,
+  pub output: Option<PathBuf>,
+  pub output_custom: Option<bool>,
+  pub inject: Option<bool>,
+  pub force: Option<bool>,
+  pub contact: Option<String>,
+  pub timeout: Option<u64>,
+  pub ca_cert: Option<PathBuf>,
+  pub curseforge_api: Option<String>,
+  pub modrinth_api: Option<String>,
+  pub max_retries: Option<u32>,
+  pub log_level: Option<String>,
+  pub color_mode: Option<String>,
+  pub sort_by: Option<String>,
+  pub reverse: Option<bool>,
+  pub keep_going: Option<bool>,
+  pub dependencies: Option<bool>,
+  pub strict: Option<bool>,
+  pub metafile_fallback: Option<bool>,
+  pub json: Option<bool>,
+  pub format: Option<String>,
+  /// Named outputs that all get written in a single run
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub outputs: BTreeMap<String, OutputConfig>,
+}
+
+/// A single output in `[outputs.<name>]`, options that aren't set fall back to [Args]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct OutputConfig {
+  /// Relative to '--path' unless 'output-custom' is set, like '--output'
+  pub path: PathBuf,
+  pub preset: Option<Preset>,
+  /// Takes priority over the preset's format
+  pub format: Option<String>,
+  pub sort_by: Option<String>,
+  pub reverse: Option<bool>,
+  /// Falls back to 'inject' like the other options
+  pub inject: Option<bool>,
+  #[serde(default)]
+  pub filter: OutputFilter,
+}
+
+impl OutputConfig {
+  pub fn to_output(&self, name: &str, args: &Args) -> GlobalResult<Output> {
+    let mut render = match self.preset {
+      Some(preset) => Render::preset(preset),
+      None => Render::from(args),
+    };
+
+    if let Some(format) = &self.format {
+      render.format = format.clone();
+    }
+
+    render.sort_by = match &self.sort_by {
+      Some(it) => Some(parse_choice("sort-by", it, &SORTING_MODES)?),
+      None => args.sort_by,
+    };
+
+    render.reverse = self.reverse.unwrap_or(args.reverse);
+    render.filter = self.filter.clone();
+
+    Ok(Output {
+      name: name.to_string(),
+      path: self.path.clone(),
+      render,
+      inject: self.inject.unwrap_or(args.inject),
+    })
+  }
+}
+
+pub fn outputs(args: &Args, outputs: &BTreeMap<String, OutputConfig>) -> GlobalResult<Vec<Output>> {
+  outputs
+    .iter()
+    .map(|(name, it)| it.to_output(name, args))
+    .collect()
+}
+
+impl From<&Args> for ProjectConfig {
+  fn from(args: &Args) -> Self {
+    Self {
+      cache: Some(args.cache.clone()),
+      shared_cache: args.shared_cache.clone(),
+      mods: Some(args.mods.clone()),
+      mods_custom: Some(args.mods_custom),
+      output: args.output.clone(),
+      output_custom: Some(args.output_custom),
+      inject: Some(args.inject),
+      force: Some(args.force),
+      contact: args.contact.clone(),
+      timeout: Some(args.timeout),
+      ca_cert: args.ca_cert.clone(),
+      curseforge_api: args.curseforge_api.clone(),
+      modrinth_api: args.modrinth_api.clone(),
+      max_retries: Some(args.max_retries),
+      log_level: Some(args.log_level.to_string()),
+      color_mode: Some(format!("{:?}", args.color_mode)),
+      sort_by: args.sort_by.map(|it| format!("{it:?}")),
+      reverse: Some(args.reverse),
+      keep_going: Some(args.keep_going),
+      dependencies: Some(args.dependencies),
+      strict: Some(args.strict),
+      metafile_fallback: Some(args.metafile_fallback),
+      json: Some(args.json),
+      format: Some(args.format.clone()),
+      outputs: BTreeMap::new(),
+    }
+  }
+}
+
+impl ProjectConfig {
+  /// Finds the config for the pack in `args.path`,
+  /// returns where it was loaded from if one was found
+  pub fn find(args: &Args) -> GlobalResult<Option<(PathBuf, Self)>> {
+    if let Some(path) = &args.config {
+      if !path.is_file() {
+        return Err(ValidationError::MustBeFile(path.clone()).into());
+      }
+
+      return Ok(Some((path.clone(), read_toml_file(path)?)));
+    }
+
+    let path = args.path.join(CONFIG_FILE);
+
+    if path.is_file() {
+      return Ok(Some((path.clone(), read_toml_file(path)?)));
+    }
+
+    let path = args.path.join("pack.toml");
+
+    if !path.is_file() {
+      return Ok(None);
+    }
+
+    let mut pack: toml::Table = read_toml_file(&path)?;
+    let config = pack
+      .get_mut("options")
+      .and_then(|it| it.as_table_mut())
+      .and_then(|it| it.remove(PACK_OPTIONS_TABLE));
+
+    match config {
+      Some(config) => Ok(Some((path, config.try_into()?))),
+      None => Ok(None),
+    }
+  }
+}
+
+/// Only values that weren't given on the command line or through the environment
+/// get replaced by the config
+fn is_default(matches: &ArgMatches, id: &str) -> bool {
+  !matches!(
+    matches.value_source(id),
+    Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+  )
+}
+
+fn parse_choice<T: FromStr>(key: &str, value: &str, values: &[&str]) -> GlobalResult<T> {
+  let invalid = || ValidationError::InvalidConfigValue {
+    key: key.to_string(),
+    value: value.to_string(),
+    expected: values.join(", "),
+  };
+
+  if !values.iter().any(|it| it.eq_ignore_ascii_case(value)) {
+    return Err(invalid().into());
+  }
+
+  value.parse().map_err(|_| invalid().into())
+}
+
+/// Paths in the config are relative to the config file
+fn relative_to(dir: &Path, path: &Path) -> PathBuf {
+  if path.is_relative() {
+    dir.join(path)
+  } else {
+    path.to_path_buf()
+  }
+}
+
+macro_rules! merge {
+  ($args:ident, $matches:ident, $($field:ident: $value:expr),* $(,)?) => {
+    $(
+      if let Some(value) = $value {
+        if is_default($matches, stringify!($field)) {
+          $args.$field = value.into();
+        }
+      }
+    )*
+  };
+}
+
+/// Loads the project config and applies it onto every option
+/// that wasn't set on the command line, returns the outputs it declares
+pub fn apply_project_config(
+  args: &mut Args,
+  matches: &ArgMatches,
+) -> GlobalResult<BTreeMap<String, OutputConfig>> {
+  let Some((path, config)) = ProjectConfig::find(args)? else {
+    return Ok(BTreeMap::new());
+  };
+
+  debug!("Using config from {}", path.display());
+
+  let dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+
+  merge!(args, matches,
+    cache: config.cache.map(|it| relative_to(&dir, &it)),
+    shared_cache: config.shared_cache.map(|it| relative_to(&dir, &it)),
+    mods: config.mods,
+    mods_custom: config.mods_custom,
+    output: config.output,
+    output_custom: config.output_custom,
+    inject: config.inject,
+    force: config.force,
+    contact: config.contact,
+    timeout: config.timeout,
+    ca_cert: config.ca_cert.map(|it| relative_to(&dir, &it)),
+    curseforge_api: config.curseforge_api,
+    modrinth_api: config.modrinth_api,
+    max_retries: config.max_retries,
+    log_level: config
+      .log_level
+      .map(|it| parse_choice::<LevelFilter>("log-level", &it, &LOG_VALUES))
+      .transpose()?,
+    color_mode: config
+      .color_mode
+      .map(|it| parse_choice::<ColorMode>("color-mode", &it, &COLOR_MODES))
+      .transpose()?,
+    sort_by: config
+      .sort_by
+      .map(|it| parse_choice::<SortingMode>("sort-by", &it, &SORTING_MODES))
+      .transpose()?,
+    reverse: config.reverse,
+    keep_going: config.keep_going,
+    dependencies: config.dependencies,
+    strict: config.strict,
+    metafile_fallback: config.metafile_fallback,
+    json: config.json,
+    format: config.format,
+  );
+
+  args.config = Some(path);
+
+  Ok(config.outputs)
+}
+
+/// Prints the effective settings as a config file
+pub fn show(args: &Args, outputs: &BTreeMap<String, OutputConfig>) -> GlobalResult<()> {
+  let config = ProjectConfig {
+    outputs: outputs.clone(),
+    ..ProjectConfig::from(args)
+  };
+
+  match &args.config {
+    Some(path) => println!("# Loaded from {}", path.display()),
+    None => println!("# No config file found"),
+  }
+
+  print!("{}", toml::to_string_pretty(&config)?);
+
+  Ok(())
+}
+
+/// Creates a config file in `args.path` from [CONFIG_TEMPLATE]
+pub fn init(args: &Args) -> GlobalResult<PathBuf> {
+  let path = args.path.join(CONFIG_FILE);
+
+  if path.exists() && !args.force {
+    return Err(ValidationError::OutputAlreadyExits(path).into());
+  }
+
+  std::fs::write(&path, CONFIG_TEMPLATE)?;
+
+  Ok(path)
+}
@@ -1,51 +1,98 @@
-use std::collections::HashMap;
-use std::path::Path;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Display;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 
+use colored::Colorize;
 use itertools::Itertools;
+use log::{debug, info, warn, LevelFilter};
 use reqwest::Client;
 use serde::de::DeserializeOwned;
 
 use GlobalError::Validation;
 
-use crate::Args;
 use crate::cache::Cache;
-use crate::error::{GlobalError, GlobalResult};
-use crate::error::ValidationError::{DirNotExist, MustBeDir, PackNotFound};
+use crate::error::ValidationError::{DirNotExist, MustBeDir, PackNotFound, ProjectsNotReturned};
+use crate::error::{GlobalError, GlobalResult, TomlFileError};
 use crate::object::{
-  CurseforgeModIds, CurseforgeMods, CurseForgeProject, ModrinthProject,
-  ModrinthTeamMember, Pack, PackMods, Project,
+  source_key, CurseForgeFile, CurseForgeProject, CurseforgeFileIds, CurseforgeFiles,
+  CurseforgeModIds, CurseforgeMods, Dependency, DependencyKind, Loader, MetafileProject,
+  ModrinthProject, ModrinthTeamMember, ModrinthVersion, Pack, PackMod, PackMods, Project, Skipped,
+  Source,
 };
+use crate::Args;
+use crate::{config, http};
 
-const CURSEFORGE_API_KEY: &str = env!("CF_API_KEY");
 const CURSEFORGE_API: &str = "https://api.curseforge.com/v1";
 const MODRINTH_API: &str = "https://api.modrinth.com/v2";
 
+fn curseforge_api(args: &Args) -> &str {
+  let url = args.curseforge_api.as_deref().unwrap_or(CURSEFORGE_API);
+
+  url.trim_end_matches('/')
+}
+
+fn modrinth_api(args: &Args) -> &str {
+  let url = args.modrinth_api.as_deref().unwrap_or(MODRINTH_API);
+
+  url.trim_end_matches('/')
+}
+
+/// Parse errors include the path and where in the file they happened
 pub fn read_toml_file<T: DeserializeOwned, P: AsRef<Path>>(path: P) -> GlobalResult<T> {
+  let path = path.as_ref();
   let data = std::fs::read_to_string(path)?;
 
-  toml::from_str::<T>(&data).map_err(GlobalError::from)
+  toml::from_str::<T>(&data).map_err(|err| TomlFileError::new(path, &data, err).into())
 }
 
-pub fn get_mods(args: &Args) -> GlobalResult<PackMods> {
-  let path = if args.mods_custom {
+pub fn mods_path(args: &Args) -> PathBuf {
+  if args.mods_custom {
     args.mods.clone()
   } else {
     args.path.join(&args.mods)
-  };
+  }
+}
+
+/// Reads every metafile, with '--keep-going' the ones that fail are skipped instead
+pub fn get_mods(args: &Args) -> GlobalResult<(PackMods, Vec<Skipped>)> {
+  let path = mods_path(args);
 
   match () {
-    _ if !path.exists() => Err(Validation(DirNotExist(path))),
-    _ if !path.is_dir() => Err(Validation(MustBeDir(path))),
-    _ => path
-      .read_dir()?
-      .filter_map(|it| it.ok())
-      .filter(|it| it.file_name().to_string_lossy().ends_with(".toml"))
-      .map(|it| read_toml_file(it.path()))
-      .collect::<GlobalResult<PackMods>>(),
+    _ if !path.exists() => return Err(Validation(DirNotExist(path))),
+    _ if !path.is_dir() => return Err(Validation(MustBeDir(path))),
+    _ => {}
+  }
+
+  let mut mods = PackMods::new();
+  let mut skipped = Vec::new();
+
+  let paths = path
+    .read_dir()?
+    .filter_map(|it| it.ok())
+    .filter(|it| it.file_name().to_string_lossy().ends_with(".toml"))
+    .map(|it| it.path());
+
+  for path in paths {
+    match read_toml_file(&path) {
+      Ok(pack_mod) => mods.push(pack_mod),
+      Err(err) if args.keep_going => {
+        warn!("Skipping {}", err.message());
+
+        skipped.push(Skipped {
+          path,
+          reason: err.message(),
+        });
+      }
+      Err(err) => return Err(err),
+    }
   }
+
+  Ok((mods, skipped))
 }
 
-pub fn get_data(args: &Args) -> GlobalResult<(Pack, PackMods)> {
+pub fn get_data(args: &Args) -> GlobalResult<(Pack, PackMods, Vec<Skipped>)> {
   let path = args.path.clone();
   let pack = path.join("pack.toml");
 
@@ -54,10 +101,10 @@ pub fn get_data(args: &Args) -> GlobalResult<(Pack, PackMods)> {
     _ if !path.is_dir() => Err(Validation(MustBeDir(path))),
     _ if !pack.is_file() => Err(Validation(PackNotFound(path))),
     _ => {
-      let mods = get_mods(args)?;
+      let (mods, skipped) = get_mods(args)?;
       let pack = read_toml_file(pack)?;
 
-      Ok((pack, mods))
+      Ok((pack, mods, skipped))
     }
   }
 }
@@ -71,32 +118,61 @@ pub fn get_data(args: &Args) -> GlobalResult<(Pack, PackMods)> {
 //   Ok(project)
 // }
 
-async fn request_modrinth_teams(ids: Vec<String>) -> GlobalResult<Vec<Vec<ModrinthTeamMember>>> {
+async fn request_modrinth_teams_chunk(
+  client: &Client,
+  args: &Args,
+  ids: Vec<String>,
+) -> GlobalResult<Vec<Vec<ModrinthTeamMember>>> {
   let ids = serde_json::to_string(&ids)?;
-  let url = format!("{MODRINTH_API}/teams?ids={ids}");
-  let response = reqwest::get(url).await?;
+  let url = format!("{}/teams?ids={ids}", modrinth_api(args));
+  let response = http::send(Source::Modrinth, client.get(url), args.max_retries).await?;
 
-  let team = response.json().await?;
+  http::json(Source::Modrinth, response).await
+}
 
-  Ok(team)
+async fn request_modrinth_teams(
+  client: &Client,
+  args: &Args,
+  ids: Vec<String>,
+) -> GlobalResult<Vec<Vec<ModrinthTeamMember>>> {
+  http::request_chunked(ids, |ids| request_modrinth_teams_chunk(client, args, ids)).await
 }
 
-async fn request_modrinth_projects(ids: Vec<String>) -> GlobalResult<Vec<ModrinthProject>> {
+async fn request_modrinth_projects_chunk(
+  client: &Client,
+  args: &Args,
+  ids: Vec<String>,
+) -> GlobalResult<Vec<ModrinthProject>> {
   let ids = serde_json::to_string(&ids)?;
-  let url = format!("{MODRINTH_API}/projects?ids={ids}");
-  let response = reqwest::get(url).await?;
-  let projects: Vec<ModrinthProject> = response.json().await?;
+  let url = format!("{}/projects?ids={ids}", modrinth_api(args));
+  let response = http::send(Source::Modrinth, client.get(url), args.max_retries).await?;
+
+  http::json(Source::Modrinth, response).await
+}
+
+async fn request_modrinth_projects(
+  client: &Client,
+  args: &Args,
+  ids: Vec<String>,
+) -> GlobalResult<Vec<ModrinthProject>> {
+  let projects = http::request_chunked(ids, |ids| {
+    request_modrinth_projects_chunk(client, args, ids)
+  })
+  .await?;
 
   let mut teams_map = projects
     .into_iter()
     .into_group_map_by(|project| project.team.clone());
 
   let ids = teams_map.keys().cloned().collect_vec();
-  let teams = request_modrinth_teams(ids).await?;
+  let teams = request_modrinth_teams(client, args, ids).await?;
 
   for team in teams.iter() {
     for member in team.iter() {
-      let projects = teams_map.get_mut(&member.team_id).unwrap();
+      // Teams that weren't asked for are ignored, like projects
+      let Some(projects) = teams_map.get_mut(&member.team_id) else {
+        continue;
+      };
 
       for project in projects {
         project.team_members.push(member.clone());
@@ -124,22 +200,34 @@ async fn request_modrinth_projects(ids: Vec<String>) -> GlobalResult<Vec<Modrint
 //   Ok(project)
 // }
 
-async fn request_curseforge_projects(ids: Vec<u32>) -> GlobalResult<Vec<CurseForgeProject>> {
+async fn request_curseforge_projects_chunk(
+  client: &Client,
+  args: &Args,
+  api_key: &str,
+  ids: Vec<u32>,
+) -> GlobalResult<Vec<CurseForgeProject>> {
   let ids = CurseforgeModIds { mod_ids: ids };
-  let url = format!("{CURSEFORGE_API}/mods");
-  let response = Client::builder()
-    .build()?
-    .post(url)
-    .header("x-api-key", CURSEFORGE_API_KEY)
-    .json(&ids)
-    .send()
-    .await?;
+  let url = format!("{}/mods", curseforge_api(args));
+  let request = client.post(url).header("x-api-key", api_key).json(&ids);
 
-  let projects: CurseforgeMods = response.json().await?;
+  let response = http::send(Source::CurseForge, request, args.max_retries).await?;
+  let projects: CurseforgeMods = http::json(Source::CurseForge, response).await?;
 
   Ok(projects.data)
 }
 
+async fn request_curseforge_projects(
+  client: &Client,
+  args: &Args,
+  api_key: &str,
+  ids: Vec<u32>,
+) -> GlobalResult<Vec<CurseForgeProject>> {
+  http::request_chunked(ids, |ids| {
+    request_curseforge_projects_chunk(client, args, api_key, ids)
+  })
+  .await
+}
+
 // #[allow(unused)]
 // pub async fn request_project(pack_mod: &PackMod) -> GlobalResult<Project> {
 //   if let Some(pack_mod) = &pack_mod.update.modrinth {
@@ -157,78 +245,556 @@ async fn request_curseforge_projects(ids: Vec<u32>) -> GlobalResult<Vec<CurseFor
 //   unreachable!()
 // }
 
-pub async fn get_modrinth_projects(
-  cache: &mut Cache,
-  mods: &PackMods,
-) -> GlobalResult<Vec<Project>> {
-  let mut modrinth = Vec::with_capacity(mods.len());
+async fn request_modrinth_versions_chunk(
+  client: &Client,
+  args: &Args,
+  ids: Vec<String>,
+) -> GlobalResult<Vec<ModrinthVersion>> {
+  let ids = serde_json::to_string(&ids)?;
+  let url = format!("{}/versions?ids={ids}", modrinth_api(args));
+  let response = http::send(Source::Modrinth, client.get(url), args.max_retries).await?;
 
-  let filter = mods.iter().filter(|it| it.update.modrinth.is_some());
+  http::json(Source::Modrinth, response).await
+}
 
-  match cache.get_all(filter.clone()) {
-    Some(projects) => modrinth.extend(projects.into_iter().cloned()),
-    None => {
-      let lookup = filter
-        .clone()
-        .into_group_map_by(|it| it.id())
-        .into_iter()
-        .map(|(key, value)| (key, value[0]))
-        .collect::<HashMap<_, _>>();
+async fn request_modrinth_versions(
+  client: &Client,
+  args: &Args,
+  ids: Vec<String>,
+) -> GlobalResult<Vec<ModrinthVersion>> {
+  http::request_chunked(ids, |ids| {
+    request_modrinth_versions_chunk(client, args, ids)
+  })
+  .await
+}
 
-      let modrinth_ids = filter.map(|it| it.id()).collect();
-      let projects = request_modrinth_projects(modrinth_ids)
-        .await?
-        .into_iter()
-        .map(|it| (lookup[&it.id], Project::from(it)));
+async fn request_curseforge_files_chunk(
+  client: &Client,
+  args: &Args,
+  api_key: &str,
+  ids: Vec<u32>,
+) -> GlobalResult<Vec<CurseForgeFile>> {
+  let ids = CurseforgeFileIds { file_ids: ids };
+  let url = format!("{}/mods/files", curseforge_api(args));
+  let request = client.post(url).header("x-api-key", api_key).json(&ids);
 
-      cache.insert_all(projects.clone());
-      modrinth.extend(projects.map(|it| it.1));
+  let response = http::send(Source::CurseForge, request, args.max_retries).await?;
+  let files: CurseforgeFiles = http::json(Source::CurseForge, response).await?;
+
+  Ok(files.data)
+}
+
+async fn request_curseforge_files(
+  client: &Client,
+  args: &Args,
+  api_key: &str,
+  ids: Vec<u32>,
+) -> GlobalResult<Vec<CurseForgeFile>> {
+  http::request_chunked(ids, |ids| {
+    request_curseforge_files_chunk(client, args, api_key, ids)
+  })
+  .await
+}
+
+/// Warns when a pinned version or file doesn't list a loader that runs on the pack's loader,
+/// versions that don't list any loaders are left alone
+fn check_pinned_loader(mods: &PackMods, key: &str, kind: &str, loader: Loader, loaders: &[String]) {
+  if loaders.is_empty() || loader.supports(loaders) {
+    return;
+  }
+
+  let name = mods
+    .iter()
+    .find(|it| it.key().as_deref() == Some(key))
+    .map_or(key, |it| it.name.as_str());
+
+  warn!(
+    "{name}'s pinned {kind} doesn't support {}, only {}",
+    loader.name(),
+    loaders.join(", ")
+  );
+}
+
+/// Prints progress to stderr, only when it's a terminal and logging isn't turned off
+fn progress(args: &Args, msg: impl Display) {
+  if args.log_level != LevelFilter::Off && std::io::stderr().is_terminal() {
+    eprintln!("{} {msg}", "::".bright_blue());
+  }
+}
+
+/// Projects for a single platform, split by where they came from
+#[derive(Debug, Default)]
+pub struct SourceProjects<'a> {
+  pub cached: Vec<Project>,
+  pub fetched: Vec<(&'a PackMod, Project)>,
+  /// Made from metafile data since they couldn't be fetched, never cached
+  pub fallback: Vec<Project>,
+  /// Requested but not returned by the API, e.g. deleted or private projects
+  pub not_returned: Vec<&'a PackMod>,
+}
+
+impl<'a> SourceProjects<'a> {
+  /// Pairs fetched projects with the mods that requested them,
+  /// anything the API returned that wasn't requested is ignored
+  fn add_fetched(&mut self, requested: &[&'a PackMod], fetched: Vec<Project>) {
+    let lookup = requested
+      .iter()
+      .filter_map(|it| Some((it.key()?, *it)))
+      .collect::<HashMap<_, _>>();
+
+    for project in fetched {
+      match lookup.get(&project.key()) {
+        Some(pack_mod) => self.fetched.push((pack_mod, project)),
+        None => debug!("Ignoring unrequested project {}", project.key()),
+      }
     }
+
+    self.not_returned = requested
+      .iter()
+      .filter(|it| {
+        !self
+          .fetched
+          .iter()
+          .any(|(pack_mod, _)| pack_mod.key() == it.key())
+      })
+      .copied()
+      .collect();
+  }
+}
+
+pub async fn get_modrinth_projects<'a>(
+  cache: &Cache,
+  client: &Client,
+  args: &Args,
+  mods: &'a PackMods,
+) -> GlobalResult<SourceProjects<'a>> {
+  let filter = mods
+    .iter()
+    .filter(|it| it.source() == Some(Source::Modrinth));
+  let (cached, missing) = cache.split(filter);
+  let mut projects = SourceProjects {
+    cached,
+    ..SourceProjects::default()
   };
 
-  Ok(modrinth)
+  if missing.is_empty() {
+    return Ok(projects);
+  }
+
+  progress(
+    args,
+    format!("Fetching {} Modrinth projects", missing.len()),
+  );
+
+  let start = Instant::now();
+  let modrinth_ids = missing.iter().filter_map(|it| it.id()).unique().collect();
+
+  let fetched = request_modrinth_projects(client, args, modrinth_ids).await?;
+
+  projects.add_fetched(&missing, fetched.into_iter().map(Project::from).collect());
+
+  progress(
+    args,
+    format!(
+      "Fetched {} Modrinth projects in {:.1}s",
+      projects.fetched.len(),
+      start.elapsed().as_secs_f32()
+    ),
+  );
+
+  Ok(projects)
 }
 
-pub async fn get_curseforge_projects(
+pub async fn get_curseforge_projects<'a>(
+  cache: &Cache,
+  client: &Client,
+  args: &Args,
+  mods: &'a PackMods,
+) -> GlobalResult<SourceProjects<'a>> {
+  let filter = mods
+    .iter()
+    .filter(|it| it.source() == Some(Source::CurseForge));
+  let (cached, missing) = cache.split(filter);
+  let mut projects = SourceProjects {
+    cached,
+    ..SourceProjects::default()
+  };
+
+  if missing.is_empty() {
+    return Ok(projects);
+  }
+
+  let Some(api_key) = config::curseforge_api_key(args) else {
+    warn!(
+      "No CurseForge API key found, using metafile data for {} CurseForge projects. \
+      Set one with {}",
+      missing.len(),
+      config::CURSEFORGE_API_KEY_SOURCES
+    );
+
+    projects.fallback = missing
+      .into_iter()
+      .filter_map(MetafileProject::new)
+      .map(Project::from)
+      .collect();
+
+    return Ok(projects);
+  };
+
+  progress(
+    args,
+    format!("Fetching {} CurseForge projects", missing.len()),
+  );
+
+  let start = Instant::now();
+  let curseforge_ids = missing
+    .iter()
+    .filter_map(|it| it.id()?.parse().ok())
+    .unique()
+    .collect();
+
+  let fetched = request_curseforge_projects(client, args, &api_key, curseforge_ids).await?;
+
+  projects.add_fetched(&missing, fetched.into_iter().map(Project::from).collect());
+
+  progress(
+    args,
+    format!(
+      "Fetched {} CurseForge projects in {:.1}s",
+      projects.fetched.len(),
+      start.elapsed().as_secs_f32()
+    ),
+  );
+
+  Ok(projects)
+}
+
+/// Warns about projects that don't list a loader that runs on the pack's loader
+pub fn check_loaders(pack: &Pack, projects: &[Project]) {
+  let Some((loader, _)) = pack.versions.loader() else {
+    return;
+  };
+
+  for project in projects {
+    let Some(loaders) = project.loaders() else {
+      continue;
+    };
+
+    if !loader.supports(loaders) {
+      warn!(
+        "{} doesn't support {}, only {}",
+        project.title(),
+        loader.name(),
+        loaders.join(", ")
+      );
+    }
+  }
+}
+
+/// Gets projects from every platform concurrently,
+/// so it only takes as long as the slowest platform
+pub async fn get_projects(
   cache: &mut Cache,
+  client: &Client,
+  args: &Args,
   mods: &PackMods,
 ) -> GlobalResult<Vec<Project>> {
-  let mut curseforge = Vec::with_capacity(mods.len());
+  let mut projects = Vec::with_capacity(mods.len());
 
-  let filter = mods.iter().filter(|it| it.update.curseforge.is_some());
+  let (modrinth, curseforge) = futures::try_join!(
+    get_modrinth_projects(cache, client, args, mods),
+    get_curseforge_projects(cache, client, args, mods),
+  )?;
+
+  // Nowhere to look these up, so they're listed with what's in their metafile
+  for pack_mod in mods.iter().filter(|it| it.source() == Some(Source::Url)) {
+    info!(
+      "{} has no [update] section, using its metafile",
+      pack_mod.name
+    );
+    projects.extend(MetafileProject::new(pack_mod).map(Project::from));
+  }
 
-  match cache.get_all(filter.clone()) {
-    Some(projects) => curseforge.extend(projects.into_iter().cloned()),
-    None => {
-      let lookup = filter
-        .clone()
-        .into_group_map_by(|it| it.id())
-        .into_iter()
-        .map(|(key, value)| (key, value[0]))
-        .collect::<HashMap<_, _>>();
+  for pack_mod in mods.iter().filter(|it| it.source().is_none()) {
+    warn!(
+      "{} has neither an [update] section nor a download url, so it's left out",
+      pack_mod.name
+    );
+  }
+
+  let mut not_returned = Vec::new();
+
+  for SourceProjects {
+    cached,
+    fetched,
+    fallback,
+    not_returned: missing,
+  } in [modrinth, curseforge]
+  {
+    projects.extend(cached);
+    projects.extend(fetched.iter().map(|it| it.1.clone()));
+    projects.extend(fallback);
+    cache.insert_all(fetched);
+    not_returned.extend(missing);
+  }
 
-      let curseforge_ids = filter.filter_map(|it| it.id().parse().ok()).collect();
-      let projects = request_curseforge_projects(curseforge_ids)
-        .await?
+  if not_returned.is_empty() {
+    return Ok(projects);
+  }
+
+  let names = not_returned
+    .iter()
+    .map(|it| format!("{} ({})", it.name, it.key().unwrap_or_default()))
+    .collect::<Vec<_>>();
+
+  if args.strict {
+    return Err(Validation(ProjectsNotReturned(names.join(", "))));
+  }
+
+  for name in &names {
+    warn!("{name} was not returned by the API, it may have been deleted or made private");
+  }
+
+  if args.metafile_fallback {
+    projects.extend(
+      not_returned
         .into_iter()
-        .map(|it| (lookup[&it.id.to_string()], Project::from(it)));
+        .filter_map(MetafileProject::new)
+        .map(Project::from),
+    );
+  }
 
-      cache.insert_all(projects.clone());
-      curseforge.extend(projects.map(|it| it.1));
-    }
+  Ok(projects)
+}
+
+/// Dependencies of the pinned Modrinth versions, by project key,
+/// versions that don't support `loader` are warned about
+async fn get_modrinth_dependencies(
+  client: &Client,
+  args: &Args,
+  mods: &PackMods,
+  loader: Option<Loader>,
+) -> GlobalResult<Vec<(String, Vec<(String, DependencyKind)>)>> {
+  let ids = mods
+    .iter()
+    .filter(|it| it.source() == Some(Source::Modrinth))
+    .filter_map(|it| Some(it.update.as_ref()?.modrinth.as_ref()?.version.clone()))
+    .unique()
+    .collect_vec();
+
+  if ids.is_empty() {
+    return Ok(Vec::new());
+  }
+
+  let versions = request_modrinth_versions(client, args, ids).await?;
+
+  let dependencies = versions
+    .into_iter()
+    .map(|version| {
+      let key = source_key(Source::Modrinth, &version.project_id);
+
+      if let Some(loader) = loader {
+        check_pinned_loader(mods, &key, "version", loader, &version.loaders);
+      }
+
+      let dependencies = version
+        .dependencies
+        .iter()
+        .filter_map(|it| {
+          Some((
+            source_key(Source::Modrinth, it.project_id.as_ref()?),
+            it.kind()?,
+          ))
+        })
+        .collect();
+
+      (key, dependencies)
+    })
+    .collect();
+
+  Ok(dependencies)
+}
+
+/// Dependencies of the pinned CurseForge files, by project key,
+/// files that don't support `loader` are warned about
+async fn get_curseforge_dependencies(
+  client: &Client,
+  args: &Args,
+  mods: &PackMods,
+  loader: Option<Loader>,
+) -> GlobalResult<Vec<(String, Vec<(String, DependencyKind)>)>> {
+  let ids = mods
+    .iter()
+    .filter(|it| it.source() == Some(Source::CurseForge))
+    .filter_map(|it| Some(it.update.as_ref()?.curseforge.as_ref()?.file_id))
+    .unique()
+    .collect_vec();
+
+  if ids.is_empty() {
+    return Ok(Vec::new());
+  }
+
+  let Some(api_key) = config::curseforge_api_key(args) else {
+    return Ok(Vec::new());
   };
 
-  Ok(curseforge)
+  let files = request_curseforge_files(client, args, &api_key, ids).await?;
+
+  let dependencies = files
+    .into_iter()
+    .map(|file| {
+      let key = source_key(Source::CurseForge, file.mod_id);
+
+      if let Some(loader) = loader {
+        check_pinned_loader(mods, &key, "file", loader, &file.loaders());
+      }
+
+      let dependencies = file
+        .dependencies
+        .iter()
+        .filter_map(|it| Some((source_key(Source::CurseForge, it.mod_id), it.kind()?)))
+        .collect();
+
+      (key, dependencies)
+    })
+    .collect();
+
+  Ok(dependencies)
 }
 
-pub async fn get_projects(cache: &mut Cache, mods: &PackMods) -> GlobalResult<Vec<Project>> {
-  let mut projects = Vec::with_capacity(mods.len());
+/// Fetches dependencies that aren't in the pack, so they can be matched by slug
+/// to the same mod from the other platform, or named in warnings
+async fn get_unknown_projects(
+  client: &Client,
+  args: &Args,
+  keys: &[&String],
+) -> GlobalResult<Vec<Project>> {
+  let ids = |source: Source| {
+    let prefix = format!("{source}:");
 
-  let modrinth = get_modrinth_projects(cache, mods).await?;
-  let curseforge = get_curseforge_projects(cache, mods).await?;
+    keys
+      .iter()
+      .filter_map(|it| it.strip_prefix(&prefix).map(String::from))
+      .collect_vec()
+  };
 
-  projects.extend_from_slice(&modrinth);
-  projects.extend_from_slice(&curseforge);
+  let mut projects = Vec::new();
+  let modrinth = ids(Source::Modrinth);
+  let curseforge = ids(Source::CurseForge)
+    .iter()
+    .filter_map(|it| it.parse().ok())
+    .collect_vec();
+
+  // Only the title and slug are needed, so their teams aren't fetched
+  if !modrinth.is_empty() {
+    let fetched = http::request_chunked(modrinth, |ids| {
+      request_modrinth_projects_chunk(client, args, ids)
+    })
+    .await?;
+
+    projects.extend(fetched.into_iter().map(Project::from));
+  }
+
+  if let Some(api_key) = (!curseforge.is_empty())
+    .then(|| config::curseforge_api_key(args))
+    .flatten()
+  {
+    let fetched = request_curseforge_projects(client, args, &api_key, curseforge).await?;
+
+    projects.extend(fetched.into_iter().map(Project::from));
+  }
 
   Ok(projects)
 }
+
+/// What each pinned version requires, by project key
+pub type PinnedDependencies = Vec<(String, Vec<(String, DependencyKind)>)>;
+
+/// Looks up the dependencies of every mod's pinned version, only needs the metafiles
+/// so it runs alongside [get_projects], pinned versions that don't support the pack's loader
+/// are warned about
+pub async fn get_pinned_dependencies(
+  client: &Client,
+  args: &Args,
+  pack: &Pack,
+  mods: &PackMods,
+) -> GlobalResult<PinnedDependencies> {
+  progress(args, "Fetching dependencies");
+
+  let loader = pack.versions.loader().map(|(loader, _)| loader);
+  let (modrinth, curseforge) = futures::try_join!(
+    get_modrinth_dependencies(client, args, mods, loader),
+    get_curseforge_dependencies(client, args, mods, loader),
+  )?;
+
+  Ok(modrinth.into_iter().chain(curseforge).collect())
+}
+
+/// Names the pinned dependencies, dependencies that are in the pack from another platform
+/// are matched by slug, required ones that are missing are warned about
+pub async fn get_dependencies(
+  client: &Client,
+  args: &Args,
+  pinned: PinnedDependencies,
+  projects: &[Project],
+) -> GlobalResult<BTreeMap<String, Vec<Dependency>>> {
+  let keys = projects.iter().map(Project::key).collect::<HashSet<_>>();
+  let unknown = pinned
+    .iter()
+    .flat_map(|it| &it.1)
+    .map(|it| &it.0)
+    .filter(|it| !keys.contains(*it))
+    .unique()
+    .collect_vec();
+
+  let unknown = match unknown.is_empty() {
+    true => Vec::new(),
+    false => get_unknown_projects(client, args, &unknown).await?,
+  };
+
+  let slugs = projects
+    .iter()
+    .map(|it| (it.slug(), it.key()))
+    .collect::<HashMap<_, _>>();
+
+  let renamed = unknown
+    .iter()
+    .filter_map(|it| Some((it.key(), slugs.get(&it.slug())?.clone())))
+    .collect::<HashMap<_, _>>();
+
+  let titles = projects
+    .iter()
+    .chain(&unknown)
+    .map(|it| (it.key(), it.title()))
+    .collect::<HashMap<_, _>>();
+
+  let title = |key: &String| titles.get(key).unwrap_or(key).clone();
+  let mut dependencies = BTreeMap::new();
+
+  for (from, found) in pinned {
+    let found = found
+      .into_iter()
+      .map(|(key, kind)| {
+        let key = renamed.get(&key).cloned().unwrap_or(key);
+
+        if kind == DependencyKind::Required && !keys.contains(&key) {
+          warn!(
+            "{} requires {}, which isn't in the pack",
+            title(&from),
+            title(&key)
+          );
+        }
+
+        Dependency {
+          name: title(&key),
+          key,
+          kind,
+        }
+      })
+      .collect();
+
+    dependencies.insert(from, found);
+  }
+
+  Ok(dependencies)
+}
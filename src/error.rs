@@ -1,9 +1,16 @@
 use std::error::Error;
-use std::path::PathBuf;
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 
-use log::error;
+use colored::Colorize;
+use reqwest::StatusCode;
+use serde::Serialize;
 use thiserror::Error;
 
+use crate::config::CURSEFORGE_API_KEY_SOURCES;
+use crate::object::Source;
+
 pub type GlobalResult<T> = Result<T, GlobalError>;
 
 #[derive(Debug, Error)]
@@ -18,6 +25,121 @@ pub enum ValidationError {
   MustBeFile(PathBuf),
   #[error("pack.toml was not found in {0}")]
   PackNotFound(PathBuf),
+  #[error("{0} is missing the 'packwizml:start' and 'packwizml:end' markers for '--inject'")]
+  MarkersNotFound(PathBuf),
+  #[error("the API did not return these projects: {0}")]
+  ProjectsNotReturned(String),
+  #[error("'--check' needs '--output' or outputs in the config to compare against")]
+  CheckWithoutOutput,
+  #[error("invalid value '{value}' for '{key}' in config, possible values: {expected}")]
+  InvalidConfigValue {
+    key: String,
+    value: String,
+    expected: String,
+  },
+}
+
+/// A problem in a toml file, with where in the file it is
+#[derive(Debug, Error)]
+pub struct TomlFileError {
+  pub path: PathBuf,
+  /// 1-based line and column of the problem
+  pub location: Option<(usize, usize)>,
+  pub message: String,
+}
+
+impl TomlFileError {
+  pub fn new(path: &Path, content: &str, err: toml::de::Error) -> Self {
+    Self::at(path, content, err.span(), err.message())
+  }
+
+  /// `span` is the byte range of the problem in `content`
+  pub fn at(path: &Path, content: &str, span: Option<Range<usize>>, message: &str) -> Self {
+    let location = span.map(|span| {
+      let before = &content[..span.start.min(content.len())];
+      let line = before.matches('\n').count() + 1;
+      let column = before.len() - before.rfind('\n').map_or(0, |it| it + 1) + 1;
+
+      (line, column)
+    });
+
+    Self {
+      path: path.to_path_buf(),
+      location,
+      message: message.trim_end().to_string(),
+    }
+  }
+}
+
+impl Display for TomlFileError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self.location {
+      Some((line, column)) => write!(f, "{}:{line}:{column}: ", self.path.display())?,
+      None => write!(f, "{}: ", self.path.display())?,
+    }
+
+    write!(f, "{}", self.message)
+  }
+}
+
+/// A platform responded, but not with what was expected
+#[derive(Debug, Error)]
+pub struct ApiError {
+  pub platform: Source,
+  /// Path of the url, the query is left out since it can contain hundreds of ids
+  pub endpoint: String,
+  pub status: StatusCode,
+  /// Start of the response body
+  pub body: String,
+  /// Why a successful response couldn't be used, e.g. it wasn't valid json
+  pub reason: Option<String>,
+}
+
+impl ApiError {
+  pub fn hint(&self) -> Option<String> {
+    match (self.platform, self.status) {
+      (Source::CurseForge, StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN) => Some(format!(
+        "check your CurseForge API key, set with {CURSEFORGE_API_KEY_SOURCES}"
+      )),
+      (_, StatusCode::NOT_FOUND) => {
+        Some("check '--curseforge-api' and '--modrinth-api' point to the right API".to_string())
+      }
+      (_, StatusCode::TOO_MANY_REQUESTS) => {
+        Some("rate limited, try again later or raise '--max-retries'".to_string())
+      }
+      (_, status) if status.is_server_error() => {
+        Some("the platform is having issues, try again later".to_string())
+      }
+      (_, status) if status.is_success() => {
+        Some("the API may have changed, check for a newer version of packwizml".to_string())
+      }
+      _ => None,
+    }
+  }
+}
+
+impl Display for ApiError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "{:?} {} returned {}",
+      self.platform, self.endpoint, self.status
+    )?;
+
+    if let Some(reason) = &self.reason {
+      write!(f, ", {reason}")?;
+    }
+
+    if !self.body.is_empty() {
+      write!(f, "\n  response: {}", self.body)?;
+    }
+
+    if let Some(hint) = self.hint() {
+      write!(f, "\n  hint: {hint}")?;
+    }
+
+    Ok(())
+  }
 }
 
 #[derive(Debug, Error)]
@@ -26,11 +148,15 @@ pub enum GlobalError {
   Validation(#[from] ValidationError),
   FileIO(#[from] std::io::Error),
   TomlDeserialize(#[from] toml::de::Error),
+  TomlFile(#[from] TomlFileError),
+  TomlSerialize(#[from] toml::ser::Error),
   JsonDeserialize(#[from] serde_json::Error),
   Clap(#[from] clap::Error),
   Reqwest(#[from] reqwest::Error),
+  Api(#[from] ApiError),
+  Watch(#[from] notify_debouncer_mini::notify::Error),
   Custom(#[from] GlobalErrorCustom),
-  Unknown(#[from] Box<dyn Error>),
+  Unknown(#[from] Box<dyn Error + Send + Sync>),
 }
 
 #[derive(Debug, Error)]
@@ -56,15 +182,89 @@ impl GlobalError {
   }
 }
 
-pub fn handle_error(err: &GlobalError) {
+/// What gets printed for an error when '--json' was given
+#[derive(Debug, Serialize)]
+struct ErrorJson<'a> {
+  kind: &'a str,
+  code: u8,
+  message: String,
+}
+
+impl GlobalError {
+  /// Category name, also used as the `kind` in json errors
+  pub fn kind(&self) -> &'static str {
+    match self {
+      GlobalError::Validation(_) => "Validation",
+      GlobalError::FileIO(_) => "File",
+      GlobalError::TomlDeserialize(_) | GlobalError::TomlSerialize(_) => "Toml",
+      GlobalError::TomlFile(_) => "Toml",
+      GlobalError::JsonDeserialize(_) => "Json",
+      GlobalError::Clap(_) => "Clap",
+      GlobalError::Reqwest(_) => "Reqwest",
+      GlobalError::Api(_) => "Api",
+      GlobalError::Watch(_) => "Watch",
+      GlobalError::Custom(_) => "Custom",
+      GlobalError::Unknown(_) => "Unknown",
+    }
+  }
+
+  /// Exit code for each category, 1 is left for '--check' finding outdated outputs
+  /// and 2 matches clap's usage errors
+  pub fn exit_code(&self) -> u8 {
+    match self {
+      GlobalError::Clap(_) => 2,
+      GlobalError::Validation(_) => 3,
+      GlobalError::FileIO(_) => 4,
+      GlobalError::TomlDeserialize(_) | GlobalError::TomlSerialize(_) => 5,
+      GlobalError::TomlFile(_) => 5,
+      GlobalError::JsonDeserialize(_) => 6,
+      GlobalError::Reqwest(_) | GlobalError::Api(_) => 7,
+      GlobalError::Watch(_) => 8,
+      GlobalError::Custom(_) | GlobalError::Unknown(_) => 9,
+    }
+  }
+
+  pub fn message(&self) -> String {
+    match self {
+      GlobalError::Validation(err) => err.to_string(),
+      GlobalError::FileIO(err) => err.to_string(),
+      GlobalError::TomlDeserialize(err) => err.to_string(),
+      GlobalError::TomlSerialize(err) => err.to_string(),
+      GlobalError::TomlFile(err) => err.to_string(),
+      GlobalError::JsonDeserialize(err) => err.to_string(),
+      GlobalError::Clap(err) => err.to_string(),
+      GlobalError::Reqwest(err) => err.to_string(),
+      GlobalError::Api(err) => err.to_string(),
+      GlobalError::Watch(err) => err.to_string(),
+      GlobalError::Custom(err) => err.to_string(),
+      GlobalError::Unknown(err) => err.to_string(),
+    }
+  }
+}
+
+/// Prints the error to stderr regardless of '--log-level',
+/// or as a json object on stdout when json output was requested
+pub fn handle_error(err: &GlobalError, json: bool) {
+  if json {
+    let error = ErrorJson {
+      kind: err.kind(),
+      code: err.exit_code(),
+      message: err.message(),
+    };
+
+    // Can't fail, every field serializes to a plain json value
+    println!("{}", serde_json::to_string_pretty(&error).unwrap());
+
+    return;
+  }
+
   match err {
-    GlobalError::Validation(err) => error!("Validation: {err}"),
-    GlobalError::FileIO(err) => error!("File: {err}"),
-    GlobalError::TomlDeserialize(err) => error!("Toml: {err}"),
-    GlobalError::JsonDeserialize(err) => error!("Json: {err}"),
-    GlobalError::Clap(err) => error!("Clap: {err}"),
-    GlobalError::Reqwest(err) => error!("Reqwest: {err}"),
-    GlobalError::Custom(err) => error!("{err}"),
-    GlobalError::Unknown(err) => error!("Unknown: {err}"),
+    GlobalError::Custom(err) => eprintln!("{} {err}", "error:".bright_red()),
+    _ => eprintln!(
+      "{} {}: {}",
+      "error:".bright_red(),
+      err.kind(),
+      err.message()
+    ),
   }
 }
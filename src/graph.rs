@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, ValueEnum};
+use itertools::Itertools;
+
+use crate::object::{Data, DependencyKind};
+use crate::Args;
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum GraphFormat {
+  #[default]
+  Dot,
+  Mermaid,
+}
+
+impl GraphFormat {
+  /// Only a '--format' given on the command line counts,
+  /// so a modlist format from the config doesn't reach the graph
+  pub fn from_matches(matches: &ArgMatches) -> Result<Self, clap::Error> {
+    if matches.value_source("format") != Some(ValueSource::CommandLine) {
+      return Ok(Self::default());
+    }
+
+    let format = matches
+      .get_one::<String>("format")
+      .map_or("", String::as_str);
+
+    Self::from_str(format, true).map_err(|_| {
+      let mut command = Args::command().bin_name("packwizml");
+
+      command.build();
+      command.find_subcommand_mut("graph").unwrap().error(
+        ErrorKind::InvalidValue,
+        format!("invalid value '{format}' for 'graph --format'\n  [possible values: dot, mermaid]"),
+      )
+    })
+  }
+}
+
+struct Node {
+  label: String,
+  /// Only there because something depends on it
+  missing: bool,
+}
+
+/// Every project in the pack and every dependency, by key
+fn nodes(data: &Data) -> BTreeMap<String, Node> {
+  let mut nodes = data
+    .projects
+    .iter()
+    .map(|it| {
+      let node = Node {
+        label: it.title(),
+        missing: false,
+      };
+
+      (it.key(), node)
+    })
+    .collect::<BTreeMap<_, _>>();
+
+  for (from, dependencies) in &data.dependencies {
+    nodes.entry(from.clone()).or_insert_with(|| Node {
+      label: from.clone(),
+      missing: true,
+    });
+
+    for it in dependencies {
+      nodes.entry(it.key.clone()).or_insert_with(|| Node {
+        label: it.name.clone(),
+        missing: true,
+      });
+    }
+  }
+
+  nodes
+}
+
+/// Nodes sorted by label, so the output doesn't change with the keys
+fn sorted(nodes: &BTreeMap<String, Node>) -> Vec<(&String, &Node)> {
+  nodes
+    .iter()
+    .sorted_by_key(|(key, node)| (node.label.to_lowercase(), *key))
+    .collect()
+}
+
+fn dot(data: &Data) -> String {
+  let quote = |it: &str| format!("\"{}\"", it.replace('\\', "\\\\").replace('"', "\\\""));
+  let mut out = String::from("digraph modlist {\n  rankdir=LR;\n");
+
+  for (key, node) in sorted(&nodes(data)) {
+    let style = if node.missing { ", style=dashed" } else { "" };
+
+    writeln!(
+      out,
+      "  {} [label={}{style}];",
+      quote(key),
+      quote(&node.label)
+    )
+    .unwrap();
+  }
+
+  for (from, dependencies) in &data.dependencies {
+    for it in dependencies {
+      let style = match it.kind {
+        DependencyKind::Required => "",
+        DependencyKind::Optional => " [style=dashed, label=\"optional\"]",
+        DependencyKind::Incompatible => " [color=red, label=\"incompatible\"]",
+      };
+
+      writeln!(out, "  {} -> {}{style};", quote(from), quote(&it.key)).unwrap();
+    }
+  }
+
+  out.push_str("}\n");
+  out
+}
+
+fn mermaid(data: &Data) -> String {
+  let nodes = nodes(data);
+  let sorted = sorted(&nodes);
+  // Keys have ':' in them, which mermaid doesn't allow in ids
+  let ids = sorted
+    .iter()
+    .enumerate()
+    .map(|(i, (key, _))| (*key, format!("n{i}")))
+    .collect::<BTreeMap<_, _>>();
+
+  let mut out = String::from("graph LR\n  classDef missing stroke-dasharray: 5 5\n");
+
+  for (key, node) in &sorted {
+    let class = if node.missing { ":::missing" } else { "" };
+    let label = node.label.replace('"', "#quot;");
+
+    writeln!(out, "  {}[\"{label}\"]{class}", ids[key]).unwrap();
+  }
+
+  for (from, dependencies) in &data.dependencies {
+    for it in dependencies {
+      let arrow = match it.kind {
+        DependencyKind::Required => "-->",
+        DependencyKind::Optional => "-.->|optional|",
+        DependencyKind::Incompatible => "--x|incompatible|",
+      };
+
+      writeln!(out, "  {} {arrow} {}", ids[from], ids[&it.key]).unwrap();
+    }
+  }
+
+  out
+}
+
+/// Renders the dependency graph, projects nothing points to and that don't point to anything
+/// are easy to spot, as are dashed nodes for dependencies missing from the pack
+pub fn render(data: &Data, format: GraphFormat) -> String {
+  match format {
+    GraphFormat::Dot => dot(data),
+    GraphFormat::Mermaid => mermaid(data),
+  }
+}
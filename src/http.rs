@@ -0,0 +1,214 @@
+use std::time::Duration;
+
+use futures::{StreamExt, TryStreamExt};
+use log::warn;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Certificate, Client, RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+
+use crate::error::{ApiError, GlobalResult};
+use crate::object::Source;
+use crate::Args;
+
+/// Max amount of ids sent in a single request
+pub const CHUNK_SIZE: usize = 100;
+/// Max amount of requests in flight at the same time
+pub const MAX_CONCURRENT_REQUESTS: usize = 4;
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Max amount of characters of a response body kept in an [ApiError]
+const BODY_SNIPPET_LEN: usize = 200;
+
+/// User-Agent in the format Modrinth asks for, `author/project/version (contact)`
+pub fn user_agent(contact: &str) -> String {
+  format!(
+    "{}/{}/{} ({contact})",
+    env!("CARGO_PKG_AUTHORS"),
+    env!("CARGO_PKG_NAME"),
+    env!("CARGO_PKG_VERSION"),
+  )
+}
+
+/// Builds the client that's shared by every request,
+/// proxies are picked up from `HTTP_PROXY`, `HTTPS_PROXY` and `NO_PROXY`
+pub fn build_client(args: &Args) -> GlobalResult<Client> {
+  let contact = args
+    .contact
+    .as_deref()
+    .unwrap_or(env!("CARGO_PKG_REPOSITORY"));
+
+  let mut builder = Client::builder()
+    .user_agent(user_agent(contact))
+    .connect_timeout(CONNECT_TIMEOUT)
+    .timeout(Duration::from_secs(args.timeout));
+
+  if let Some(path) = &args.ca_cert {
+    let pem = std::fs::read(path)?;
+
+    builder = builder.add_root_certificate(Certificate::from_pem(&pem)?);
+  }
+
+  Ok(builder.build()?)
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+  matches!(
+    status,
+    StatusCode::TOO_MANY_REQUESTS
+      | StatusCode::INTERNAL_SERVER_ERROR
+      | StatusCode::BAD_GATEWAY
+      | StatusCode::SERVICE_UNAVAILABLE
+      | StatusCode::GATEWAY_TIMEOUT
+  )
+}
+
+fn header_secs(headers: &HeaderMap, name: &str) -> Option<u64> {
+  headers.get(name)?.to_str().ok()?.trim().parse().ok()
+}
+
+/// Gets how long the server wants us to wait from `Retry-After`,
+/// or from the rate-limit headers if there are no requests remaining
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+  if let Some(secs) = header_secs(headers, RETRY_AFTER.as_str()) {
+    return Some(Duration::from_secs(secs));
+  }
+
+  match header_secs(headers, "x-ratelimit-remaining") {
+    Some(0) => header_secs(headers, "x-ratelimit-reset").map(Duration::from_secs),
+    _ => None,
+  }
+}
+
+fn backoff(attempt: u32) -> Duration {
+  INITIAL_BACKOFF
+    .saturating_mul(2u32.saturating_pow(attempt))
+    .min(MAX_BACKOFF)
+}
+
+fn snippet(body: &str) -> String {
+  let body = body.trim();
+
+  match body.char_indices().nth(BODY_SNIPPET_LEN) {
+    Some((end, _)) => format!("{}...", &body[..end]),
+    None => body.to_string(),
+  }
+}
+
+async fn api_error(platform: Source, response: Response) -> ApiError {
+  let endpoint = response.url().path().to_string();
+  let status = response.status();
+  let body = response.text().await.unwrap_or_default();
+
+  ApiError {
+    platform,
+    endpoint,
+    status,
+    body: snippet(&body),
+    reason: None,
+  }
+}
+
+/// Reads a successful response as json, a body that doesn't match `T` becomes an [ApiError]
+pub async fn json<T: DeserializeOwned>(platform: Source, response: Response) -> GlobalResult<T> {
+  let endpoint = response.url().path().to_string();
+  let status = response.status();
+  let body = response.text().await?;
+
+  serde_json::from_str(&body).map_err(|err| {
+    ApiError {
+      platform,
+      endpoint,
+      status,
+      body: snippet(&body),
+      reason: Some(format!("invalid response: {err}")),
+    }
+    .into()
+  })
+}
+
+/// Sends a request, retrying up to `max_retries` times on rate limits,
+/// transient server errors and connection errors with exponential backoff,
+/// responses that still fail become an [ApiError]
+pub async fn send(
+  platform: Source,
+  request: RequestBuilder,
+  max_retries: u32,
+) -> GlobalResult<Response> {
+  let mut attempt = 0;
+
+  loop {
+    // Only fails for streaming bodies, which are never used
+    let result = request.try_clone().unwrap().send().await;
+
+    let delay = match result {
+      Ok(response) if is_retryable(response.status()) && attempt < max_retries => {
+        let delay = match retry_after(response.headers()) {
+          // Waiting that long looks like a hang, so it fails like any other error
+          Some(delay) if delay > MAX_BACKOFF => {
+            let mut err = api_error(platform, response).await;
+
+            err.reason = Some(format!(
+              "asked to wait {}s, longer than the {}s retries wait at most",
+              delay.as_secs(),
+              MAX_BACKOFF.as_secs()
+            ));
+
+            return Err(err.into());
+          }
+          Some(delay) => delay,
+          None => backoff(attempt),
+        };
+
+        warn!(
+          "{} returned {}, retrying in {:.1}s",
+          response.url(),
+          response.status(),
+          delay.as_secs_f32()
+        );
+
+        delay
+      }
+      Ok(response) if !response.status().is_success() => {
+        return Err(api_error(platform, response).await.into());
+      }
+      Ok(response) => return Ok(response),
+      Err(err) if (err.is_timeout() || err.is_connect()) && attempt < max_retries => {
+        let delay = backoff(attempt);
+
+        warn!("{err}, retrying in {:.1}s", delay.as_secs_f32());
+
+        delay
+      }
+      Err(err) => return Err(err.into()),
+    };
+
+    tokio::time::sleep(delay).await;
+    attempt += 1;
+  }
+}
+
+/// Splits `ids` into chunks of [CHUNK_SIZE] and requests them concurrently,
+/// with at most [MAX_CONCURRENT_REQUESTS] in flight
+pub async fn request_chunked<I, T, F, Fut>(ids: Vec<I>, request: F) -> GlobalResult<Vec<T>>
+where
+  I: Clone,
+  F: Fn(Vec<I>) -> Fut,
+  Fut: std::future::Future<Output = GlobalResult<Vec<T>>>,
+{
+  let chunks = ids
+    .chunks(CHUNK_SIZE)
+    .map(|it| it.to_vec())
+    .collect::<Vec<_>>();
+
+  let results = futures::stream::iter(chunks)
+    .map(request)
+    .buffer_unordered(MAX_CONCURRENT_REQUESTS)
+    .try_collect::<Vec<_>>()
+    .await?;
+
+  Ok(results.into_iter().flatten().collect())
+}
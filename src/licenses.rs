@@ -0,0 +1,98 @@
+use std::collections::{BTreeMap, HashMap};
+
+use colored::Colorize;
+
+use crate::object::{Data, Side};
+use crate::verify::is_for_side;
+
+#[derive(Debug, Default)]
+pub struct Report {
+  /// Project names by license name
+  pub licenses: BTreeMap<String, Vec<String>>,
+  /// Projects without a known license, e.g. every CurseForge project
+  pub unknown: Vec<String>,
+  /// Projects that don't allow third-party distribution, so they can't go in a server pack
+  pub disallowed: Vec<String>,
+  /// Modrinth projects that are all rights reserved, only noted since the author
+  /// may still allow distribution
+  pub all_rights_reserved: Vec<String>,
+}
+
+impl Report {
+  pub fn is_ok(&self) -> bool {
+    self.disallowed.is_empty()
+  }
+
+  pub fn print(&self) {
+    let groups = self
+      .licenses
+      .iter()
+      .map(|(license, names)| (license.as_str(), names))
+      .chain((!self.unknown.is_empty()).then_some(("Unknown", &self.unknown)));
+
+    for (license, names) in groups {
+      println!("{} ({})", license.bright_cyan(), names.len());
+
+      for name in names {
+        println!("  {name}");
+      }
+    }
+
+    for name in &self.disallowed {
+      println!(
+        "{} {name} doesn't allow third-party distribution",
+        "disallowed".bright_red()
+      );
+    }
+
+    for name in &self.all_rights_reserved {
+      println!(
+        "{} {name} is all rights reserved, check it allows distribution",
+        "note".bright_yellow()
+      );
+    }
+  }
+}
+
+/// Groups the projects meant for `side` by license
+pub fn licenses(data: &Data, side: Option<Side>) -> Report {
+  let sides = data
+    .mods
+    .iter()
+    .filter_map(|it| Some((it.key()?, it.side)))
+    .collect::<HashMap<_, _>>();
+
+  let mut projects = data
+    .projects
+    .iter()
+    .filter(|it| sides.get(&it.key()).is_none_or(|it| is_for_side(side, *it)))
+    .collect::<Vec<_>>();
+
+  projects.sort_by_key(|it| it.title().to_lowercase());
+
+  let mut report = Report::default();
+
+  for project in projects {
+    if project
+      .license()
+      .is_some_and(|it| it.is_all_rights_reserved())
+    {
+      report.all_rights_reserved.push(project.title());
+    }
+
+    match project.license() {
+      Some(license) => report
+        .licenses
+        .entry(license.name())
+        .or_default()
+        .push(project.title()),
+      None => report.unknown.push(project.title()),
+    }
+
+    if project.allows_distribution() == Some(false) {
+      report.disallowed.push(project.title());
+    }
+  }
+
+  report
+}
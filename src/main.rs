@@ -1,26 +1,68 @@
 extern crate core;
 
+use std::collections::BTreeMap;
 use std::fmt::Display;
+use std::io::stdout;
+use std::process::ExitCode;
 
 use cache::Cache;
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches};
 use colored::Colorize;
+use log::info;
 use simple_logger::SimpleLogger;
 
-use crate::args::{Args, ColorMode};
+use crate::args::{Args, CacheCommand, ColorMode, Command, ConfigCommand};
+use crate::config::OutputConfig;
 use crate::error::{handle_error, GlobalError, GlobalResult, ValidationError};
-use crate::output::{generate, write};
+use crate::output::generate_and_write;
 
 mod args;
 mod cache;
+mod compare;
+mod config;
 mod data;
 mod error;
+mod graph;
+mod http;
+mod licenses;
 mod object;
 mod output;
+mod validate;
+mod verify;
+mod watch;
 
 #[tokio::main]
-async fn main() {
-  let args = Args::parse();
+async fn main() -> ExitCode {
+  let matches = Args::command().get_matches();
+  let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+
+  if let Some(Command::Graph { format }) = &mut args.command {
+    *format = graph::GraphFormat::from_matches(&matches).unwrap_or_else(|err| err.exit());
+  }
+
+  // Handled before the config and API key are loaded, so a broken config can't break them
+  match args.command {
+    Some(Command::Completions { shell }) => {
+      clap_complete::generate(shell, &mut Args::command(), "packwizml", &mut stdout());
+
+      return ExitCode::SUCCESS;
+    }
+    Some(Command::Man) => {
+      return match clap_mangen::Man::new(Args::command()).render(&mut stdout()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+          let err = GlobalError::from(err);
+
+          handle_error(&err, args.json);
+          ExitCode::from(err.exit_code())
+        }
+      };
+    }
+    _ => {}
+  }
+
+  // Applied before anything else since it can change logging and colors
+  let config = config::apply_project_config(&mut args, &matches);
 
   match args.color_mode {
     ColorMode::Auto => (),
@@ -36,6 +78,14 @@ async fn main() {
     .init()
     .unwrap();
 
+  let outputs = match config {
+    Ok(outputs) => outputs,
+    Err(err) => {
+      handle_error(&err, args.json);
+      return ExitCode::from(err.exit_code());
+    }
+  };
+
   if args.about {
     fn about(k: &str, v: impl Display) {
       println!("{}{}{}", k.bright_purple(), ": ".white(), v);
@@ -48,28 +98,158 @@ async fn main() {
     about("License", env!("CARGO_PKG_LICENSE").bright_cyan());
     about("Repository", env!("CARGO_PKG_REPOSITORY").bright_blue());
 
-    return;
+    return ExitCode::SUCCESS;
   }
 
-  let result = run(&args).await;
+  let json = args.json;
+  let result = run(args, outputs).await;
 
-  if let Err(err) = result {
-    handle_error(&err);
+  match result {
+    Ok(code) => code,
+    Err(err) => {
+      handle_error(&err, json);
+      ExitCode::from(err.exit_code())
+    }
   }
 }
 
-async fn run(args: &Args) -> GlobalResult<()> {
-  let mut cache = Cache::new(args).await?;
-  let data = generate(&mut cache, args).await?;
+async fn run(mut args: Args, outputs: BTreeMap<String, OutputConfig>) -> GlobalResult<ExitCode> {
+  config::resolve(&mut args)?;
+
+  let args = &args;
+
+  match args.command.clone().unwrap_or(Command::Generate) {
+    Command::Generate => run_generate(args, &outputs).await,
+    Command::Check => {
+      let args = Args {
+        check: true,
+        ..args.clone()
+      };
+
+      run_generate(&args, &outputs).await
+    }
+    Command::Config { command } => match command {
+      ConfigCommand::Show => config::show(args, &outputs).map(|_| ExitCode::SUCCESS),
+    },
+    Command::Cache { command } => match command {
+      CacheCommand::Path => {
+        println!("{}", args.cache.display());
+
+        if let Some(path) = &args.shared_cache {
+          println!("{}", path.display());
+        }
+
+        Ok(ExitCode::SUCCESS)
+      }
+      CacheCommand::Clear => {
+        if Cache::clear(args).await? {
+          info!("Deleted {}", args.cache.display());
+        }
+
+        Ok(ExitCode::SUCCESS)
+      }
+    },
+    Command::Completions { .. } | Command::Man => unreachable!("handled in main"),
+    Command::Validate => {
+      let (problems, files) = validate::validate(args)?;
+
+      for problem in &problems {
+        println!("{problem}");
+      }
+
+      if !problems.is_empty() {
+        return Ok(ExitCode::FAILURE);
+      }
+
+      info!("No problems found in {files} files");
+
+      Ok(ExitCode::SUCCESS)
+    }
+    Command::Verify { dir, side } => {
+      let report = verify::verify(args, &dir, side)?;
+
+      report.print();
+
+      if !report.is_ok() {
+        return Ok(ExitCode::FAILURE);
+      }
+
+      info!("Verified {} files", report.verified);
+
+      Ok(ExitCode::SUCCESS)
+    }
+    Command::Compare { dir, side } => {
+      let report = compare::compare(args, &dir, side)?;
+
+      report.print();
 
-  if args.json {
-    println!("{}", serde_json::to_string_pretty(&data).unwrap());
+      if !report.is_ok() {
+        return Ok(ExitCode::FAILURE);
+      }
 
-    return Ok(());
+      info!("All {} mods match", report.matched);
+
+      Ok(ExitCode::SUCCESS)
+    }
+    Command::Graph { format } => {
+      let args = &Args {
+        dependencies: true,
+        ..args.clone()
+      };
+
+      let client = http::build_client(args)?;
+      let mut cache = Cache::new(args).await?;
+      let data = output::generate(&mut cache, &client, args).await?;
+
+      cache.save(args).await?;
+      print!("{}", graph::render(&data, format));
+
+      Ok(ExitCode::SUCCESS)
+    }
+    Command::Licenses { side } => {
+      let client = http::build_client(args)?;
+      let mut cache = Cache::new(args).await?;
+      let data = output::generate(&mut cache, &client, args).await?;
+
+      cache.save(args).await?;
+
+      let report = licenses::licenses(&data, side);
+
+      report.print();
+
+      if !report.is_ok() {
+        return Ok(ExitCode::FAILURE);
+      }
+
+      Ok(ExitCode::SUCCESS)
+    }
+    Command::Init => {
+      let path = config::init(args)?;
+
+      info!("Created {}", path.display());
+
+      Ok(ExitCode::SUCCESS)
+    }
   }
+}
 
-  write(args, &data).await?;
-  cache.save(args).await?;
+async fn run_generate(
+  args: &Args,
+  outputs: &BTreeMap<String, OutputConfig>,
+) -> GlobalResult<ExitCode> {
+  let outputs = config::outputs(args, outputs)?;
 
-  Ok(())
+  let client = http::build_client(args)?;
+  let mut cache = Cache::new(args).await?;
+
+  if args.watch {
+    watch::watch(&mut cache, &client, args, &outputs).await?;
+
+    return Ok(ExitCode::SUCCESS);
+  }
+
+  match generate_and_write(&mut cache, &client, args, &outputs).await? {
+    true => Ok(ExitCode::SUCCESS),
+    false => Ok(ExitCode::FAILURE),
+  }
 }
@@ -1,44 +1,253 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+
 use serde::{Deserialize, Serialize};
 
+/// `pack.toml`, see https://packwiz.infra.link/reference/pack-format/pack-toml/
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Pack {
   pub name: String,
-  pub author: String,
-  pub version: String,
+  pub author: Option<String>,
+  pub version: Option<String>,
+  pub description: Option<String>,
   #[serde(alias = "pack-format")]
-  pub pack_format: String,
+  pub pack_format: Option<String>,
+  pub index: PackIndex,
   pub versions: PackVersions,
+  /// Free-form, other tools (including this one) keep their settings here
+  #[serde(default, skip_serializing_if = "toml::Table::is_empty")]
+  pub options: toml::Table,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackIndex {
+  pub file: String,
+  #[serde(alias = "hash-format")]
+  pub hash_format: HashFormat,
+  pub hash: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PackVersions {
+  pub minecraft: String,
   pub fabric: Option<String>,
   pub forge: Option<String>,
-  pub minecraft: String,
+  pub quilt: Option<String>,
+  pub neoforge: Option<String>,
+  pub liteloader: Option<String>,
+}
+
+impl PackVersions {
+  /// The pack's loader and its version, packs normally only have one
+  pub fn loader(&self) -> Option<(Loader, &str)> {
+    [
+      (Loader::NeoForge, &self.neoforge),
+      (Loader::Forge, &self.forge),
+      (Loader::Quilt, &self.quilt),
+      (Loader::Fabric, &self.fabric),
+      (Loader::LiteLoader, &self.liteloader),
+    ]
+    .into_iter()
+    .find_map(|(loader, version)| Some((loader, version.as_deref()?)))
+  }
+}
+
+/// Mod loaders packwiz supports
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Loader {
+  Fabric,
+  Forge,
+  Quilt,
+  NeoForge,
+  LiteLoader,
+}
+
+impl Loader {
+  pub const ALL: [Loader; 5] = [
+    Loader::Fabric,
+    Loader::Forge,
+    Loader::Quilt,
+    Loader::NeoForge,
+    Loader::LiteLoader,
+  ];
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      Loader::Fabric => "Fabric",
+      Loader::Forge => "Forge",
+      Loader::Quilt => "Quilt",
+      Loader::NeoForge => "NeoForge",
+      Loader::LiteLoader => "LiteLoader",
+    }
+  }
+
+  /// Loaders whose mods also run on this one, e.g. Quilt runs Fabric mods
+  pub fn runs(&self) -> &'static [Loader] {
+    match self {
+      Loader::Quilt => &[Loader::Quilt, Loader::Fabric],
+      Loader::Fabric => &[Loader::Fabric],
+      Loader::Forge => &[Loader::Forge],
+      Loader::NeoForge => &[Loader::NeoForge],
+      Loader::LiteLoader => &[Loader::LiteLoader],
+    }
+  }
+
+  /// Whether mods for any of `loaders` run on this one, names are compared ignoring case
+  pub fn supports(&self, loaders: &[String]) -> bool {
+    self.runs().iter().any(|it| {
+      loaders
+        .iter()
+        .any(|name| name.eq_ignore_ascii_case(it.name()))
+    })
+  }
+}
+
+/// Same as the names Modrinth uses for loaders
+impl Display for Loader {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.name().to_lowercase())
+  }
+}
+
+/// Hash formats packwiz supports
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashFormat {
+  Sha1,
+  Sha256,
+  Sha512,
+  Md5,
+  Murmur2,
+}
+
+impl HashFormat {
+  /// Checks the hash looks like this format, hex of the right length or a number for murmur2
+  pub fn is_valid(&self, hash: &str) -> bool {
+    let hex = |len: usize| hash.len() == len && hash.chars().all(|it| it.is_ascii_hexdigit());
+
+    match self {
+      HashFormat::Sha1 => hex(40),
+      HashFormat::Sha256 => hex(64),
+      HashFormat::Sha512 => hex(128),
+      HashFormat::Md5 => hex(32),
+      HashFormat::Murmur2 => hash.parse::<u32>().is_ok(),
+    }
+  }
+}
+
+impl Display for HashFormat {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      HashFormat::Sha1 => write!(f, "sha1"),
+      HashFormat::Sha256 => write!(f, "sha256"),
+      HashFormat::Sha512 => write!(f, "sha512"),
+      HashFormat::Md5 => write!(f, "md5"),
+      HashFormat::Murmur2 => write!(f, "murmur2"),
+    }
+  }
+}
+
+/// Where a mod is needed, packwiz treats a missing side as both
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+  Client,
+  Server,
+  #[default]
+  Both,
+}
+
+impl Display for Side {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Side::Client => write!(f, "client"),
+      Side::Server => write!(f, "server"),
+      Side::Both => write!(f, "both"),
+    }
+  }
+}
+
+/// Platform a project is hosted on
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+  CurseForge,
+  Modrinth,
+  /// Downloaded straight from `download.url`, there's no platform to look it up on
+  Url,
+}
+
+impl Display for Source {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Source::CurseForge => write!(f, "curseforge"),
+      Source::Modrinth => write!(f, "modrinth"),
+      Source::Url => write!(f, "url"),
+    }
+  }
+}
+
+/// Creates a source-qualified key, e.g. `modrinth:AANobbMI` or `curseforge:238222`
+pub fn source_key(source: Source, id: impl Display) -> String {
+  format!("{source}:{id}")
 }
 
 pub type PackMods = Vec<PackMod>;
 
+/// A mod's metafile, see https://packwiz.infra.link/reference/pack-format/mod-toml/
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PackMod {
   pub name: String,
   pub filename: String,
-  pub side: String,
+  #[serde(default)]
+  pub side: Side,
   pub download: PackModDownload,
-  pub update: PackModUpdate,
+  /// Missing for mods that were added from a plain url
+  pub update: Option<PackModUpdate>,
+  pub option: Option<PackModOption>,
 }
 
 impl PackMod {
-  pub fn id(&self) -> String {
-    if let Some(pack_mod) = &self.update.modrinth {
-      pack_mod.mod_id.clone()
-    } else if let Some(pack_mod) = &self.update.curseforge {
-      pack_mod.project_id.to_string()
+  /// Id on the platform it's updated from, Modrinth is preferred if there's both,
+  /// mods without an `[update]` section use their filename
+  pub fn id(&self) -> Option<String> {
+    match self.source()? {
+      Source::Modrinth => Some(self.update.as_ref()?.modrinth.as_ref()?.mod_id.clone()),
+      Source::CurseForge => Some(
+        self
+          .update
+          .as_ref()?
+          .curseforge
+          .as_ref()?
+          .project_id
+          .to_string(),
+      ),
+      Source::Url => Some(self.filename.clone()),
+    }
+  }
+
+  pub fn source(&self) -> Option<Source> {
+    let update = self.update.as_ref();
+
+    if update.is_some_and(|it| it.modrinth.is_some()) {
+      Some(Source::Modrinth)
+    } else if update.is_some_and(|it| it.curseforge.is_some()) {
+      Some(Source::CurseForge)
+    } else if self.download.url.is_some() {
+      Some(Source::Url)
     } else {
-      unreachable!()
+      None
     }
   }
 
+  /// Source-qualified id, so ids from different platforms can't collide
+  pub fn key(&self) -> Option<String> {
+    Some(source_key(self.source()?, self.id()?))
+  }
+
   pub fn hash(&self) -> &String {
     &self.download.hash
   }
@@ -46,9 +255,20 @@ impl PackMod {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PackModDownload {
+  /// Can only be left out with `mode = "metadata:curseforge"`
+  pub url: Option<String>,
   pub hash: String,
   #[serde(alias = "hash-format")]
-  pub hash_format: String,
+  pub hash_format: HashFormat,
+  pub mode: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackModOption {
+  pub optional: bool,
+  #[serde(default)]
+  pub default: bool,
+  pub description: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -69,7 +289,6 @@ pub struct PackModUpdateCurseforge {
 pub struct PackModUpdateModrinth {
   #[serde(alias = "mod-id")]
   pub mod_id: String,
-  #[serde(alias = "project-id")]
   pub version: String,
 }
 
@@ -82,7 +301,22 @@ pub struct CurseForgeProject {
   pub summary: String,
   #[serde(default)]
   pub authors: Vec<CurseForgeAuthor>,
-  pub logo: Option<CurseForgeLogo>
+  pub logo: Option<CurseForgeLogo>,
+  /// Whether third-party launchers and server packs may download or bundle the files,
+  /// null for projects that never set it
+  #[serde(default, rename = "allowModDistribution")]
+  pub allow_mod_distribution: Option<bool>,
+  #[serde(default)]
+  pub categories: Vec<CurseForgeCategory>,
+}
+
+/// CurseForge's "API and Library" category for Minecraft mods
+const CURSEFORGE_LIBRARY_CATEGORY: u32 = 421;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurseForgeCategory {
+  pub id: u32,
+  pub name: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -115,6 +349,48 @@ pub struct ModrinthProject {
   pub source_url: Option<String>,
   pub title: String,
   pub description: String,
+  /// Not in caches made by older versions
+  #[serde(default)]
+  pub loaders: Vec<String>,
+  /// Not in caches made by older versions
+  #[serde(default)]
+  pub license: Option<ModrinthLicense>,
+  /// Not in caches made by older versions
+  #[serde(default)]
+  pub categories: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModrinthLicense {
+  /// SPDX id, or `LicenseRef-*` for licenses SPDX doesn't have
+  pub id: String,
+  pub name: String,
+  pub url: Option<String>,
+}
+
+impl ModrinthLicense {
+  /// `LicenseRef-` licenses don't always have a name, so one is made from the id
+  pub fn name(&self) -> String {
+    match self.id.strip_prefix("LicenseRef-") {
+      Some(_) if !self.name.is_empty() => self.name.clone(),
+      Some(id) => id.replace('-', " "),
+      None => self.id.clone(),
+    }
+  }
+
+  pub fn is_all_rights_reserved(&self) -> bool {
+    self.id == "LicenseRef-All-Rights-Reserved"
+  }
+
+  pub fn url(&self) -> Option<String> {
+    match &self.url {
+      Some(url) => Some(url.clone()),
+      None if !self.id.starts_with("LicenseRef-") => {
+        Some(format!("https://spdx.org/licenses/{}.html", self.id))
+      }
+      None => None,
+    }
+  }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -134,12 +410,41 @@ pub struct ModrinthTeamMemberUser {
   pub bio: Option<String>,
 }
 
+/// Project made only from what's in a mod's metafile,
+/// for when it couldn't be fetched or there's nowhere to fetch it from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetafileProject {
+  pub source: Source,
+  pub id: String,
+  pub name: String,
+  /// Download url of mods from [Source::Url]
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub url: Option<String>,
+}
+
+impl MetafileProject {
+  /// Only mods with an `[update]` section or a download url know where they're from
+  pub fn new(pack_mod: &PackMod) -> Option<Self> {
+    let source = pack_mod.source()?;
+
+    Some(Self {
+      source,
+      id: pack_mod.id()?,
+      name: pack_mod.name.clone(),
+      url: (source == Source::Url)
+        .then(|| pack_mod.download.url.clone())
+        .flatten(),
+    })
+  }
+}
+
 pub type Projects = Vec<Project>;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Project {
   CurseForge(CurseForgeProject),
   Modrinth(ModrinthProject),
+  Metafile(MetafileProject),
 }
 
 impl From<CurseForgeProject> for Project {
@@ -154,6 +459,12 @@ impl From<ModrinthProject> for Project {
   }
 }
 
+impl From<MetafileProject> for Project {
+  fn from(project: MetafileProject) -> Self {
+    Project::Metafile(project)
+  }
+}
+
 impl Project {
   pub fn url(&self) -> String {
     match self {
@@ -161,6 +472,13 @@ impl Project {
         format!("https://www.curseforge.com/minecraft/mc-mods/{slug}")
       }
       Project::Modrinth(ModrinthProject { id, .. }) => format!("https://modrinth.com/mod/{id}"),
+      Project::Metafile(MetafileProject {
+        source, id, url, ..
+      }) => match source {
+        Source::CurseForge => format!("https://www.curseforge.com/projects/{id}"),
+        Source::Modrinth => format!("https://modrinth.com/mod/{id}"),
+        Source::Url => url.clone().unwrap_or_default(),
+      },
     }
   }
 
@@ -168,13 +486,28 @@ impl Project {
     match self {
       Project::CurseForge(CurseForgeProject { id, .. }) => id.to_string(),
       Project::Modrinth(ModrinthProject { id, .. }) => id.clone(),
+      Project::Metafile(MetafileProject { id, .. }) => id.clone(),
+    }
+  }
+
+  pub fn source(&self) -> Source {
+    match self {
+      Project::CurseForge(_) => Source::CurseForge,
+      Project::Modrinth(_) => Source::Modrinth,
+      Project::Metafile(MetafileProject { source, .. }) => *source,
     }
   }
 
+  /// Source-qualified id, so ids from different platforms can't collide
+  pub fn key(&self) -> String {
+    source_key(self.source(), self.id())
+  }
+
   pub fn slug(&self) -> String {
     match self {
       Project::CurseForge(CurseForgeProject { slug, .. }) => slug.clone(),
       Project::Modrinth(ModrinthProject { slug, .. }) => slug.clone(),
+      Project::Metafile(MetafileProject { id, .. }) => id.clone(),
     }
   }
 
@@ -182,6 +515,50 @@ impl Project {
     match self {
       Project::CurseForge(CurseForgeProject { name, .. }) => name.clone(),
       Project::Modrinth(ModrinthProject { title, .. }) => title.clone(),
+      Project::Metafile(MetafileProject { name, .. }) => name.clone(),
+    }
+  }
+
+  /// Loaders the project says it supports, only known for Modrinth projects
+  pub fn loaders(&self) -> Option<&[String]> {
+    match self {
+      Project::Modrinth(ModrinthProject { loaders, .. }) if !loaders.is_empty() => Some(loaders),
+      _ => None,
+    }
+  }
+
+  /// Whether the platform lists the project as a library, `None` when it has no categories
+  pub fn library_category(&self) -> Option<bool> {
+    match self {
+      Project::Modrinth(ModrinthProject { categories, .. }) if !categories.is_empty() => {
+        Some(categories.iter().any(|it| it == "library"))
+      }
+      Project::CurseForge(CurseForgeProject { categories, .. }) if !categories.is_empty() => Some(
+        categories
+          .iter()
+          .any(|it| it.id == CURSEFORGE_LIBRARY_CATEGORY),
+      ),
+      _ => None,
+    }
+  }
+
+  /// Only known for Modrinth projects, CurseForge's API doesn't have licenses
+  pub fn license(&self) -> Option<&ModrinthLicense> {
+    match self {
+      Project::Modrinth(ModrinthProject { license, .. }) => license.as_ref(),
+      _ => None,
+    }
+  }
+
+  /// Whether CurseForge allows the project to be bundled in a server pack,
+  /// `None` when that's unknown, Modrinth doesn't have a flag for it
+  pub fn allows_distribution(&self) -> Option<bool> {
+    match self {
+      Project::CurseForge(CurseForgeProject {
+        allow_mod_distribution,
+        ..
+      }) => *allow_mod_distribution,
+      _ => None,
     }
   }
 
@@ -189,24 +566,194 @@ impl Project {
     match self {
       Project::CurseForge(CurseForgeProject { summary, .. }) => summary.clone(),
       Project::Modrinth(ModrinthProject { description, .. }) => description.clone(),
+      Project::Metafile(_) => String::new(),
     }
   }
 }
 
+/// Something left out of the modlist with '--keep-going'
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Skipped {
+  pub path: PathBuf,
+  pub reason: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Data {
   pub pack: Pack,
   pub mods: PackMods,
   pub projects: Projects,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub skipped: Vec<Skipped>,
+  /// Dependencies of each project by key, only filled with '--dependencies'
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub dependencies: BTreeMap<String, Vec<Dependency>>,
+}
+
+impl Data {
+  fn title(&self, key: &str) -> String {
+    self
+      .projects
+      .iter()
+      .find(|it| it.key() == key)
+      .map_or_else(|| key.to_string(), Project::title)
+  }
+
+  fn requires<'a>(&'a self, key: &'a str) -> impl Iterator<Item = (&'a String, &'a Dependency)> {
+    self
+      .dependencies
+      .iter()
+      .flat_map(|(from, it)| it.iter().map(move |it| (from, it)))
+      .filter(move |(_, it)| it.kind == DependencyKind::Required && it.key == key)
+  }
+
+  /// Names of what `project` requires
+  pub fn dependencies_of(&self, project: &Project) -> Vec<String> {
+    self
+      .dependencies
+      .get(&project.key())
+      .into_iter()
+      .flatten()
+      .filter(|it| it.kind == DependencyKind::Required)
+      .map(|it| it.name.clone())
+      .collect()
+  }
+
+  /// Names of every project that requires `project`
+  pub fn required_by(&self, project: &Project) -> Vec<String> {
+    self
+      .requires(&project.key())
+      .map(|(from, _)| self.title(from))
+      .collect()
+  }
+
+  /// Libraries are in the pack because another project requires them,
+  /// unless the platform lists them as something else, e.g. Sodium added for Iris isn't one
+  pub fn is_library(&self, project: &Project) -> bool {
+    project.library_category() != Some(false) && self.requires(&project.key()).next().is_some()
+  }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CurseforgeModIds {
-  pub mod_ids: Vec<u32>
+  pub mod_ids: Vec<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CurseforgeMods {
-  pub data: Vec<CurseForgeProject>
-}
\ No newline at end of file
+  pub data: Vec<CurseForgeProject>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseforgeFileIds {
+  pub file_ids: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurseforgeFiles {
+  pub data: Vec<CurseForgeFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgeFile {
+  pub id: u32,
+  pub mod_id: u32,
+  #[serde(default)]
+  pub dependencies: Vec<CurseForgeFileDependency>,
+  /// Minecraft versions mixed with loader names, e.g. `1.20.4` and `Fabric`
+  #[serde(default)]
+  pub game_versions: Vec<String>,
+}
+
+impl CurseForgeFile {
+  /// The loaders in [CurseForgeFile::game_versions], lowercase like Modrinth's
+  pub fn loaders(&self) -> Vec<String> {
+    Loader::ALL
+      .iter()
+      .filter(|it| {
+        self
+          .game_versions
+          .iter()
+          .any(|version| version.eq_ignore_ascii_case(it.name()))
+      })
+      .map(Loader::to_string)
+      .collect()
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurseForgeFileDependency {
+  pub mod_id: u32,
+  pub relation_type: u32,
+}
+
+impl CurseForgeFileDependency {
+  /// Embedded libraries, tools and includes aren't separate mods in the pack
+  pub fn kind(&self) -> Option<DependencyKind> {
+    match self.relation_type {
+      2 => Some(DependencyKind::Optional),
+      3 => Some(DependencyKind::Required),
+      5 => Some(DependencyKind::Incompatible),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModrinthVersion {
+  pub id: String,
+  pub project_id: String,
+  #[serde(default)]
+  pub dependencies: Vec<ModrinthDependency>,
+  #[serde(default)]
+  pub loaders: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModrinthDependency {
+  pub project_id: Option<String>,
+  pub version_id: Option<String>,
+  pub dependency_type: String,
+}
+
+impl ModrinthDependency {
+  /// Embedded dependencies are inside the jar, so they aren't separate mods in the pack
+  pub fn kind(&self) -> Option<DependencyKind> {
+    match self.dependency_type.as_str() {
+      "required" => Some(DependencyKind::Required),
+      "optional" => Some(DependencyKind::Optional),
+      "incompatible" => Some(DependencyKind::Incompatible),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyKind {
+  Required,
+  Optional,
+  Incompatible,
+}
+
+impl Display for DependencyKind {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      DependencyKind::Required => write!(f, "required"),
+      DependencyKind::Optional => write!(f, "optional"),
+      DependencyKind::Incompatible => write!(f, "incompatible"),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Dependency {
+  pub key: String,
+  /// Title of the project, or its key if it couldn't be found
+  pub name: String,
+  pub kind: DependencyKind,
+}
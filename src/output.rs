@@ -1,44 +1,245 @@
-use log::info;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use colored::Colorize;
+use log::{info, warn};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use similar::{ChangeTag, TextDiff};
 use tokio::fs::File;
 use tokio::io::{stdout, AsyncWrite, AsyncWriteExt};
 
 use crate::args::SortingMode;
 use crate::cache::Cache;
-use crate::data::{get_data, get_projects};
-use crate::object::{Data, Project};
+use crate::data::{
+  check_loaders, get_data, get_dependencies, get_pinned_dependencies, get_projects,
+};
+use crate::object::{Data, PackMod, Project, Side, Source};
 use crate::{Args, GlobalError, GlobalResult, ValidationError};
 
-pub fn display_project(index: usize, format: &str, project: &Project) -> String {
+pub const MARKDOWN_FORMAT: &str = "- [{NAME}]({URL}) - {DESCRIPTION}\n";
+pub const HTML_FORMAT: &str = "<li><a href=\"{URL}\">{NAME}</a> - {DESCRIPTION}</li>\n";
+
+/// Markers around the region '--inject' replaces
+pub const INJECT_START: &str = "<!-- packwizml:start -->";
+pub const INJECT_END: &str = "<!-- packwizml:end -->";
+
+/// Built-in output formats
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Preset {
+  Markdown,
+  Html,
+  Json,
+}
+
+/// Which projects end up in an output, an empty list allows everything
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct OutputFilter {
+  /// Only include mods for these sides, e.g. `["client", "both"]`
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub sides: Vec<Side>,
+  /// Only include projects from these platforms
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub sources: Vec<Source>,
+  /// Leave out projects by slug or id
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub exclude: Vec<String>,
+  /// Only include libraries when true, leave them out when false, needs '--dependencies'
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub libraries: Option<bool>,
+}
+
+impl OutputFilter {
+  pub fn allows(&self, project: &Project, pack_mod: Option<&PackMod>, is_library: bool) -> bool {
+    self.libraries.is_none_or(|it| it == is_library)
+      && (self.sides.is_empty() || pack_mod.is_some_and(|it| self.sides.contains(&it.side)))
+      && (self.sources.is_empty() || self.sources.contains(&project.source()))
+      && !self
+        .exclude
+        .iter()
+        .any(|it| *it == project.slug() || *it == project.id())
+  }
+}
+
+/// Everything needed to render a list of projects
+#[derive(Debug, Clone)]
+pub struct Render {
+  pub format: String,
+  pub json: bool,
+  /// Escapes values for the html preset
+  pub html: bool,
+  pub sort_by: Option<SortingMode>,
+  pub reverse: bool,
+  pub filter: OutputFilter,
+}
+
+impl From<&Args> for Render {
+  fn from(args: &Args) -> Self {
+    Self {
+      format: args.format.clone(),
+      json: false,
+      html: false,
+      sort_by: args.sort_by,
+      reverse: args.reverse,
+      filter: OutputFilter::default(),
+    }
+  }
+}
+
+impl Render {
+  pub fn preset(preset: Preset) -> Self {
+    Self {
+      format: match preset {
+        Preset::Html => HTML_FORMAT,
+        Preset::Markdown | Preset::Json => MARKDOWN_FORMAT,
+      }
+      .to_string(),
+      json: preset == Preset::Json,
+      html: preset == Preset::Html,
+      sort_by: None,
+      reverse: false,
+      filter: OutputFilter::default(),
+    }
+  }
+}
+
+/// A named output declared in the config
+#[derive(Debug, Clone)]
+pub struct Output {
+  pub name: String,
+  pub path: PathBuf,
+  pub render: Render,
+  /// Only replaces the region between [INJECT_START] and [INJECT_END]
+  pub inject: bool,
+}
+
+/// Escapes text so it can't break out of, or inject, markup
+fn escape_html(text: &str) -> String {
+  text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+    .replace('\'', "&#39;")
+}
+
+/// `data` is everything, so dependencies that were filtered out still get their names,
+/// values are escaped when `html` is set
+pub fn display_project(
+  index: usize,
+  format: &str,
+  data: &Data,
+  project: &Project,
+  html: bool,
+) -> String {
+  let loader = data.pack.versions.loader();
+  let license = project.license();
+  let value = |it: &str| match html {
+    true => escape_html(it),
+    false => it.to_string(),
+  };
+
   format
     .replace("{INDEX}", &index.to_string())
-    .replace("{TITLE}", &project.title())
-    .replace("{NAME}", &project.title())
-    .replace("{DESCRIPTION}", &project.description())
-    .replace("{SUMMARY}", &project.description())
-    .replace("{URL}", &project.url())
-    .replace("{ID}", &project.id())
-    .replace("{SLUG}", &project.slug())
+    .replace("{TITLE}", &value(&project.title()))
+    .replace("{NAME}", &value(&project.title()))
+    .replace("{DESCRIPTION}", &value(&project.description()))
+    .replace("{SUMMARY}", &value(&project.description()))
+    .replace("{URL}", &value(&project.url()))
+    .replace("{ID}", &value(&project.id()))
+    .replace("{SLUG}", &value(&project.slug()))
+    .replace("{LOADER}", loader.map_or("", |it| it.0.name()))
+    .replace("{LOADER_VERSION}", &value(loader.map_or("", |it| it.1)))
+    .replace(
+      "{DEPENDENCIES}",
+      &value(&data.dependencies_of(project).join(", ")),
+    )
+    .replace(
+      "{REQUIRED_BY}",
+      &value(&data.required_by(project).join(", ")),
+    )
+    .replace(
+      "{LICENSE_URL}",
+      &value(&license.and_then(|it| it.url()).unwrap_or_default()),
+    )
+    .replace(
+      "{LICENSE}",
+      &value(&license.map(|it| it.name()).unwrap_or_default()),
+    )
     .replace("\\n", "\n")
 }
 
-pub async fn generate(cache: &mut Cache, args: &Args) -> GlobalResult<Data> {
-  let (pack, mods) = get_data(args)?;
-  let projects = get_projects(cache, &mods).await?;
+pub async fn generate(cache: &mut Cache, client: &Client, args: &Args) -> GlobalResult<Data> {
+  let (pack, mods, skipped) = get_data(args)?;
+
+  // The pinned versions only need the metafiles, so they're fetched alongside the projects
+  let pinned = async {
+    match args.dependencies {
+      true => get_pinned_dependencies(client, args, &pack, &mods).await,
+      false => Ok(Vec::new()),
+    }
+  };
+  let (projects, pinned) = futures::try_join!(get_projects(cache, client, args, &mods), pinned)?;
+
+  // The pinned versions are more precise than the project, so they're checked when fetched
+  let dependencies = match args.dependencies {
+    true => get_dependencies(client, args, pinned, &projects).await?,
+    false => {
+      check_loaders(&pack, &projects);
+      BTreeMap::new()
+    }
+  };
 
   Ok(Data {
     pack,
     mods,
     projects,
+    skipped,
+    dependencies,
   })
 }
 
-pub async fn write_projects<W>(args: &Args, data: &Data, writer: &mut W) -> GlobalResult<()>
+/// Only keeps the projects (and their mods) the filter allows
+pub fn filter_data(data: &Data, filter: &OutputFilter) -> Data {
+  let mods = data
+    .mods
+    .iter()
+    .filter_map(|it| Some((it.key()?, it)))
+    .collect::<HashMap<_, _>>();
+
+  let projects = data
+    .projects
+    .iter()
+    .filter(|it| filter.allows(it, mods.get(&it.key()).copied(), data.is_library(it)))
+    .cloned()
+    .collect::<Vec<_>>();
+
+  let keys = projects.iter().map(Project::key).collect::<Vec<_>>();
+
+  Data {
+    pack: data.pack.clone(),
+    mods: data
+      .mods
+      .iter()
+      .filter(|it| it.key().is_some_and(|key| keys.contains(&key)))
+      .cloned()
+      .collect(),
+    projects,
+    skipped: data.skipped.clone(),
+    dependencies: data.dependencies.clone(),
+  }
+}
+
+pub async fn write_projects<W>(render: &Render, data: &Data, writer: &mut W) -> GlobalResult<()>
 where
   W: AsyncWrite + Unpin,
 {
-  let mut projects = data.projects.clone();
+  let filtered = filter_data(data, &render.filter);
+  let mut projects = filtered.projects.clone();
 
-  if let Some(mode) = args.sort_by {
+  if let Some(mode) = render.sort_by {
     projects.sort_by(|a, b| match mode {
       SortingMode::Name | SortingMode::Title => {
         a.title().to_lowercase().cmp(&b.title().to_lowercase())
@@ -48,12 +249,28 @@ where
     });
   }
 
-  if args.reverse {
+  if render.reverse {
     projects.reverse();
   }
 
+  // Libraries are grouped after everything else
+  projects.sort_by_key(|it| data.is_library(it));
+
+  if render.json {
+    let data = Data {
+      projects,
+      ..filtered
+    };
+    let json = serde_json::to_string_pretty(&data)?;
+
+    writer.write_all(json.as_bytes()).await?;
+    writer.write_all(b"\n").await?;
+
+    return Ok(());
+  }
+
   for (index, project) in projects.iter().enumerate() {
-    let display = display_project(index, &args.format, project);
+    let display = display_project(index, &render.format, data, project, render.html);
 
     info!("{display}");
 
@@ -63,29 +280,196 @@ where
   Ok(())
 }
 
+/// Replaces everything between the markers in `existing` with `content`
+pub fn inject(path: &Path, existing: &str, content: &str) -> GlobalResult<String> {
+  let not_found = || ValidationError::MarkersNotFound(path.to_path_buf());
+  let start = existing.find(INJECT_START).ok_or_else(not_found)? + INJECT_START.len();
+  let end = existing[start..].find(INJECT_END).ok_or_else(not_found)? + start;
+  let newline = if content.ends_with('\n') { "" } else { "\n" };
+
+  Ok(format!(
+    "{}\n{content}{newline}{}",
+    &existing[..start],
+    &existing[end..]
+  ))
+}
+
+/// Updates only the marked region of an existing file, so `force` isn't needed
+pub async fn inject_file(path: &Path, render: &Render, data: &Data) -> GlobalResult<()> {
+  if !path.is_file() {
+    return Err(ValidationError::MustBeFile(path.to_path_buf()).into());
+  }
+
+  let existing = tokio::fs::read_to_string(path).await?;
+  let content = self::render(render, data).await?;
+
+  tokio::fs::write(path, inject(path, &existing, &content)?).await?;
+
+  Ok(())
+}
+
+pub async fn write_file(
+  path: PathBuf,
+  force: bool,
+  render: &Render,
+  data: &Data,
+) -> GlobalResult<()> {
+  if path.exists() && !force {
+    return Err(GlobalError::Validation(
+      ValidationError::OutputAlreadyExits(path),
+    ));
+  }
+
+  let mut file = File::create(path).await?;
+
+  write_projects(render, data, &mut file).await?;
+  file.flush().await?;
+
+  Ok(())
+}
+
+pub fn output_path(args: &Args, path: &Path) -> PathBuf {
+  if args.output_custom {
+    path.to_path_buf()
+  } else {
+    args.path.join(path)
+  }
+}
+
 pub async fn write(args: &Args, data: &Data) -> GlobalResult<()> {
+  let render = Render::from(args);
+
   match &args.output {
-    Some(path) => {
-      let path = if args.output_custom {
-        path.clone()
-      } else {
-        args.path.join(&path)
-      };
-
-      if path.exists() && !args.force {
-        return Err(GlobalError::Validation(
-          ValidationError::OutputAlreadyExits(path),
-        ));
+    Some(path) if args.inject => inject_file(&output_path(args, path), &render, data).await?,
+    Some(path) => write_file(output_path(args, path), args.force, &render, data).await?,
+    None => {
+      write_projects(&render, data, &mut stdout()).await?;
+    }
+  }
+
+  Ok(())
+}
+
+/// Writes every output declared in the config
+pub async fn write_outputs(args: &Args, outputs: &[Output], data: &Data) -> GlobalResult<()> {
+  for output in outputs {
+    let path = output_path(args, &output.path);
+
+    info!("Writing output '{}' to {}", output.name, path.display());
+
+    if output.inject {
+      inject_file(&path, &output.render, data).await?;
+    } else {
+      write_file(path, args.force, &output.render, data).await?;
+    }
+  }
+
+  Ok(())
+}
+
+/// Renders into memory instead of writing anywhere
+pub async fn render(render: &Render, data: &Data) -> GlobalResult<String> {
+  let mut bytes = Vec::new();
+
+  write_projects(render, data, &mut bytes).await?;
+
+  Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Prints a colored unified diff of `old` to `new`
+fn print_diff(path: &Path, old: &str, new: &str) {
+  let name = path.display().to_string();
+  let diff = TextDiff::from_lines(old, new);
+
+  println!("{}", format!("--- {name}").bright_red());
+  println!("{}", format!("+++ {name} (generated)").bright_green());
+
+  for hunk in diff.unified_diff().iter_hunks() {
+    println!("{}", hunk.header().to_string().bright_cyan());
+
+    for change in hunk.iter_changes() {
+      let line = change.to_string_lossy();
+      let line = line.trim_end_matches('\n');
+
+      match change.tag() {
+        ChangeTag::Delete => println!("{}", format!("-{line}").bright_red()),
+        ChangeTag::Insert => println!("{}", format!("+{line}").bright_green()),
+        ChangeTag::Equal => println!(" {line}"),
       }
+    }
+  }
+}
+
+/// Compares every output with what's on disk, returns if they're all up to date
+pub async fn check(args: &Args, outputs: &[Output], data: &Data) -> GlobalResult<bool> {
+  let targets = match &args.output {
+    Some(path) => vec![(output_path(args, path), Render::from(args), args.inject)],
+    None if !outputs.is_empty() => outputs
+      .iter()
+      .map(|it| (output_path(args, &it.path), it.render.clone(), it.inject))
+      .collect(),
+    None => return Err(ValidationError::CheckWithoutOutput.into()),
+  };
+
+  let mut up_to_date = true;
 
-      let mut file = File::create(path).await?;
+  for (path, render, inject) in targets {
+    let actual = match path.is_file() {
+      true => tokio::fs::read_to_string(&path).await?,
+      false if inject => return Err(ValidationError::MustBeFile(path).into()),
+      false => String::new(),
+    };
 
-      write_projects(args, data, &mut file).await?;
+    let expected = match inject {
+      true => self::inject(&path, &actual, &self::render(&render, data).await?)?,
+      false => self::render(&render, data).await?,
+    };
+
+    if expected != actual {
+      up_to_date = false;
+      print_diff(&path, &actual, &expected);
+    } else {
+      info!("{} is up to date", path.display());
     }
-    None => {
-      write_projects(args, data, &mut stdout()).await?;
+  }
+
+  Ok(up_to_date)
+}
+
+/// Generates the data and writes it to every output, or stdout,
+/// returns false when '--check' found outdated outputs
+pub async fn generate_and_write(
+  cache: &mut Cache,
+  client: &Client,
+  args: &Args,
+  outputs: &[Output],
+) -> GlobalResult<bool> {
+  let data = generate(cache, client, args).await?;
+  let mut up_to_date = true;
+
+  if args.check {
+    up_to_date = check(args, outputs, &data).await?;
+  } else if args.json {
+    println!("{}", serde_json::to_string_pretty(&data)?);
+  } else if outputs.is_empty() || args.output.is_some() {
+    // Config outputs are written instead of stdout, unless '--output' was given
+    write(args, &data).await?;
+  } else {
+    write_outputs(args, outputs, &data).await?;
+  }
+
+  // '--check' has no side effects, not even on the cache
+  if !args.check {
+    cache.save(args).await?;
+  }
+
+  if !data.skipped.is_empty() {
+    warn!("Skipped {} metafiles:", data.skipped.len());
+
+    for skipped in &data.skipped {
+      warn!("  {}", skipped.path.display());
     }
   }
 
-  Ok(())
+  Ok(up_to_date)
 }
@@ -0,0 +1,157 @@
+use std::ops::Range;
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use toml_edit::{ImDocument, Item};
+
+use crate::data::mods_path;
+use crate::error::{GlobalResult, TomlFileError, ValidationError};
+use crate::object::{Pack, PackMod};
+use crate::Args;
+
+/// Problems found by a check, with the keys of what they're about
+type Problems = Vec<(&'static [&'static str], String)>;
+
+/// Finds the byte range of a nested key, e.g. `["download", "hash"]`
+fn span(doc: &ImDocument<&str>, keys: &[&str]) -> Option<Range<usize>> {
+  keys
+    .iter()
+    .try_fold(doc.as_item(), |item: &Item, key| item.get(key))?
+    .span()
+}
+
+/// Parses a file as `T`, then runs `check` on it if it's valid toml of the right shape
+fn validate_file<T, F>(path: &Path, check: F) -> GlobalResult<Vec<TomlFileError>>
+where
+  T: DeserializeOwned,
+  F: Fn(&T) -> Problems,
+{
+  let content = std::fs::read_to_string(path)?;
+
+  let doc = match ImDocument::parse(content.as_str()) {
+    Ok(doc) => doc,
+    Err(err) => {
+      return Ok(vec![TomlFileError::at(
+        path,
+        &content,
+        err.span(),
+        err.message(),
+      )])
+    }
+  };
+
+  let value = match toml::from_str::<T>(&content) {
+    Ok(value) => value,
+    Err(err) => return Ok(vec![TomlFileError::new(path, &content, err)]),
+  };
+
+  let problems = check(&value)
+    .into_iter()
+    .map(|(keys, message)| TomlFileError::at(path, &content, span(&doc, keys), &message))
+    .collect();
+
+  Ok(problems)
+}
+
+fn check_pack(dir: &Path, pack: &Pack) -> Problems {
+  let mut problems = Problems::new();
+
+  // Optional, packwiz assumes the current format when it's left out
+  if let Some(format) = pack
+    .pack_format
+    .as_ref()
+    .filter(|it| !it.starts_with("packwiz:"))
+  {
+    problems.push((
+      &["pack-format"],
+      format!("pack-format should look like 'packwiz:1.1.0', found '{format}'"),
+    ));
+  }
+
+  if !dir.join(&pack.index.file).is_file() {
+    problems.push((
+      &["index", "file"],
+      format!("index file '{}' does not exist", pack.index.file),
+    ));
+  }
+
+  if let Some(hash) = &pack.index.hash {
+    if !pack.index.hash_format.is_valid(hash) {
+      problems.push((
+        &["index", "hash"],
+        format!("'{hash}' is not a valid {} hash", pack.index.hash_format),
+      ));
+    }
+  }
+
+  problems
+}
+
+fn check_mod(pack_mod: &PackMod) -> Problems {
+  let mut problems = Problems::new();
+  let download = &pack_mod.download;
+
+  if pack_mod.filename.trim().is_empty() {
+    problems.push((&["filename"], "filename can't be empty".to_string()));
+  }
+
+  if !download.hash_format.is_valid(&download.hash) {
+    problems.push((
+      &["download", "hash"],
+      format!(
+        "'{}' is not a valid {} hash",
+        download.hash, download.hash_format
+      ),
+    ));
+  }
+
+  if download.url.is_none() && download.mode.as_deref() != Some("metadata:curseforge") {
+    problems.push((
+      &["download"],
+      "download needs a url, unless mode is 'metadata:curseforge'".to_string(),
+    ));
+  }
+
+  if let Some(update) = &pack_mod.update {
+    if update.modrinth.is_none() && update.curseforge.is_none() {
+      problems.push((
+        &["update"],
+        "update needs a 'modrinth' or 'curseforge' table".to_string(),
+      ));
+    }
+  }
+
+  problems
+}
+
+/// Checks 'pack.toml' and every metafile against packwiz's spec,
+/// returns every problem found and how many files were checked
+pub fn validate(args: &Args) -> GlobalResult<(Vec<TomlFileError>, usize)> {
+  let pack = args.path.join("pack.toml");
+  let mods = mods_path(args);
+
+  if !pack.is_file() {
+    return Err(ValidationError::PackNotFound(args.path.clone()).into());
+  }
+
+  let mut problems = validate_file(&pack, |it| check_pack(&args.path, it))?;
+  let mut files = 1;
+
+  if mods.is_dir() {
+    let mut paths = mods
+      .read_dir()?
+      .filter_map(|it| it.ok())
+      .filter(|it| it.file_name().to_string_lossy().ends_with(".toml"))
+      .map(|it| it.path())
+      .collect::<Vec<_>>();
+
+    paths.sort();
+
+    for path in paths {
+      problems.extend(validate_file(&path, check_mod)?);
+      files += 1;
+    }
+  }
+
+  Ok((problems, files))
+}
@@ -0,0 +1,176 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use colored::Colorize;
+use md5::Md5;
+use sha1::{Digest, Sha1};
+use sha2::{Sha256, Sha512};
+
+use crate::data::get_mods;
+use crate::error::{GlobalResult, ValidationError};
+use crate::object::{HashFormat, Side};
+use crate::Args;
+
+/// Seed CurseForge uses for its murmur2 fingerprints
+const MURMUR2_SEED: u32 = 1;
+
+/// CurseForge's murmur2 fingerprint, which skips whitespace bytes before hashing
+fn murmur2(data: &[u8]) -> u32 {
+  const M: u32 = 0x5bd1e995;
+  const R: u32 = 24;
+
+  let data = data
+    .iter()
+    .copied()
+    .filter(|it| !matches!(it, 9 | 10 | 13 | 32))
+    .collect::<Vec<_>>();
+
+  let mut h = MURMUR2_SEED ^ data.len() as u32;
+  let chunks = data.chunks_exact(4);
+  let rest = chunks.remainder();
+
+  for chunk in chunks {
+    let mut k = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+
+    k = k.wrapping_mul(M);
+    k ^= k >> R;
+    k = k.wrapping_mul(M);
+
+    h = h.wrapping_mul(M);
+    h ^= k;
+  }
+
+  if !rest.is_empty() {
+    for (i, byte) in rest.iter().enumerate() {
+      h ^= (*byte as u32) << (8 * i);
+    }
+
+    h = h.wrapping_mul(M);
+  }
+
+  h ^= h >> 13;
+  h = h.wrapping_mul(M);
+  h ^= h >> 15;
+
+  h
+}
+
+/// Hashes `data` the way packwiz writes it for `format`
+pub fn hash(format: HashFormat, data: &[u8]) -> String {
+  match format {
+    HashFormat::Sha1 => format!("{:x}", Sha1::digest(data)),
+    HashFormat::Sha256 => format!("{:x}", Sha256::digest(data)),
+    HashFormat::Sha512 => format!("{:x}", Sha512::digest(data)),
+    HashFormat::Md5 => format!("{:x}", Md5::digest(data)),
+    HashFormat::Murmur2 => murmur2(data).to_string(),
+  }
+}
+
+#[derive(Debug)]
+pub struct Mismatch {
+  pub filename: String,
+  pub format: HashFormat,
+  pub expected: String,
+  pub actual: String,
+}
+
+/// Result of comparing a directory of jars with the metafiles
+#[derive(Debug, Default)]
+pub struct Report {
+  pub verified: usize,
+  /// In a metafile but not in the directory
+  pub missing: Vec<String>,
+  /// In the directory but not in any metafile
+  pub extra: Vec<String>,
+  pub mismatched: Vec<Mismatch>,
+}
+
+impl Report {
+  pub fn is_ok(&self) -> bool {
+    self.missing.is_empty() && self.extra.is_empty() && self.mismatched.is_empty()
+  }
+
+  pub fn print(&self) {
+    for filename in &self.missing {
+      println!("{} {filename}", "missing ".bright_red());
+    }
+
+    for Mismatch {
+      filename,
+      format,
+      expected,
+      actual,
+    } in &self.mismatched
+    {
+      println!(
+        "{} {filename}, expected {format} {expected}, got {actual}",
+        "mismatch".bright_red()
+      );
+    }
+
+    for filename in &self.extra {
+      println!("{} {filename}", "extra   ".bright_yellow());
+    }
+  }
+}
+
+/// Only mods meant for `side` are expected, e.g. a server won't have client only mods
+pub fn is_for_side(side: Option<Side>, mod_side: Side) -> bool {
+  match side {
+    None | Some(Side::Both) => true,
+    Some(side) => mod_side == side || mod_side == Side::Both,
+  }
+}
+
+/// Compares the jars in `dir` with the hashes in the metafiles
+pub fn verify(args: &Args, dir: &Path, side: Option<Side>) -> GlobalResult<Report> {
+  match () {
+    _ if !dir.exists() => return Err(ValidationError::DirNotExist(dir.to_path_buf()).into()),
+    _ if !dir.is_dir() => return Err(ValidationError::MustBeDir(dir.to_path_buf()).into()),
+    _ => {}
+  }
+
+  let (mods, _) = get_mods(args)?;
+  let mut report = Report::default();
+
+  let mut files = dir
+    .read_dir()?
+    .filter_map(|it| it.ok())
+    .filter(|it| it.file_type().is_ok_and(|it| it.is_file()))
+    .map(|it| it.file_name().to_string_lossy().into_owned())
+    .collect::<BTreeSet<_>>();
+
+  let mut mods = mods
+    .iter()
+    .filter(|it| is_for_side(side, it.side))
+    .collect::<Vec<_>>();
+
+  mods.sort_by(|a, b| a.filename.cmp(&b.filename));
+
+  for pack_mod in mods {
+    let filename = &pack_mod.filename;
+
+    if !files.remove(filename) {
+      report.missing.push(filename.clone());
+      continue;
+    }
+
+    let format = pack_mod.download.hash_format;
+    let actual = hash(format, &std::fs::read(dir.join(filename))?);
+
+    if actual.eq_ignore_ascii_case(pack_mod.hash()) {
+      report.verified += 1;
+    } else {
+      report.mismatched.push(Mismatch {
+        filename: filename.clone(),
+        format,
+        expected: pack_mod.hash().clone(),
+        actual,
+      });
+    }
+  }
+
+  report.extra = files.into_iter().collect();
+
+  Ok(report)
+}
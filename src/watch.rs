@@ -0,0 +1,99 @@
+use std::path::Path;
+use std::time::Duration;
+
+use log::{debug, info};
+use notify_debouncer_mini::notify::RecursiveMode;
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult};
+use reqwest::Client;
+use tokio::sync::mpsc;
+
+use crate::cache::Cache;
+use crate::data::mods_path;
+use crate::error::{handle_error, GlobalResult};
+use crate::output::{generate_and_write, Output};
+use crate::Args;
+
+/// How long to wait for changes to settle before regenerating,
+/// packwiz usually touches several files at once
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Files in '--path' that trigger a regeneration
+const PACK_FILES: [&str; 2] = ["pack.toml", "index.toml"];
+
+fn is_relevant(args: &Args, mods: &Path, path: &Path) -> bool {
+  if path.starts_with(mods) {
+    return path.extension().is_some_and(|it| it == "toml");
+  }
+
+  PACK_FILES.iter().any(|it| path == args.path.join(it))
+}
+
+/// Outputs written by a successful run are ours to overwrite,
+/// until then they're only overwritten with '--force'
+async fn regenerate(cache: &mut Cache, client: &Client, args: &mut Args, outputs: &[Output]) {
+  match generate_and_write(cache, client, args, outputs).await {
+    Ok(_) => args.force = true,
+    Err(err) => handle_error(&err, args.json),
+  }
+}
+
+/// Generates once, then again every time the pack changes.
+/// Unchanged mods come from the cache, so only changed ones get fetched.
+pub async fn watch(
+  cache: &mut Cache,
+  client: &Client,
+  args: &Args,
+  outputs: &[Output],
+) -> GlobalResult<()> {
+  let (tx, mut rx) = mpsc::unbounded_channel();
+  let mut debouncer = new_debouncer(DEBOUNCE, move |result: DebounceEventResult| {
+    let _ = tx.send(result);
+  })?;
+
+  // Paths from events are absolute, so these need to be too
+  let mut args = Args {
+    path: std::fs::canonicalize(&args.path)?,
+    ..args.clone()
+  };
+
+  let mods = std::fs::canonicalize(mods_path(&args))?;
+
+  // The pack directory isn't watched recursively, since outputs usually live there
+  debouncer
+    .watcher()
+    .watch(&args.path, RecursiveMode::NonRecursive)?;
+  debouncer.watcher().watch(&mods, RecursiveMode::Recursive)?;
+
+  regenerate(cache, client, &mut args, outputs).await;
+
+  info!("Watching {} for changes", args.path.display());
+
+  while let Some(result) = rx.recv().await {
+    let events = match result {
+      Ok(events) => events,
+      Err(err) => {
+        handle_error(&err.into(), args.json);
+        continue;
+      }
+    };
+
+    let changed = events
+      .iter()
+      .filter(|it| is_relevant(&args, &mods, &it.path))
+      .collect::<Vec<_>>();
+
+    if changed.is_empty() {
+      continue;
+    }
+
+    for event in changed {
+      debug!("Changed {}", event.path.display());
+    }
+
+    info!("Regenerating");
+
+    regenerate(cache, client, &mut args, outputs).await;
+  }
+
+  Ok(())
+}
@@ -0,0 +1,1676 @@
+//! Runs `packwizml` end to end against a local fake server serving recorded responses
+
+use std::path::{Path, PathBuf};
+use std::process::Output;
+
+use tempfile::TempDir;
+use tokio::process::Command;
+use wiremock::matchers::{header, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const EXPECTED: &str = "\
+- [Just Enough Items (JEI)](https://www.curseforge.com/minecraft/mc-mods/jei) - View Items and Recipes
+- [Lithium](https://modrinth.com/mod/gvQqBUqZ) - No-compromises game logic optimization mod
+- [Sodium](https://modrinth.com/mod/AANobbMI) - The fastest and most compatible rendering optimization mod for Minecraft
+";
+
+fn fixtures() -> PathBuf {
+  Path::new(env!("CARGO_MANIFEST_DIR"))
+    .join("tests")
+    .join("fixtures")
+}
+
+fn response(name: &str) -> ResponseTemplate {
+  let body = std::fs::read_to_string(fixtures().join("responses").join(name)).unwrap();
+
+  ResponseTemplate::new(200).set_body_raw(body, "application/json")
+}
+
+struct Fixture {
+  server: MockServer,
+  dir: TempDir,
+  pack: PathBuf,
+}
+
+impl Fixture {
+  /// Starts a server that serves every recorded response
+  async fn new() -> Self {
+    let fixture = Self::empty().await;
+
+    fixture.mount_modrinth().await;
+    fixture.mount_curseforge().await;
+
+    fixture
+  }
+
+  /// Starts a server without any responses
+  async fn empty() -> Self {
+    Self {
+      server: MockServer::start().await,
+      dir: TempDir::new().unwrap(),
+      pack: fixtures().join("pack"),
+    }
+  }
+
+  async fn mount_modrinth(&self) {
+    Mock::given(method("GET"))
+      .and(path("/modrinth/projects"))
+      .respond_with(response("modrinth_projects.json"))
+      .mount(&self.server)
+      .await;
+
+    Mock::given(method("GET"))
+      .and(path("/modrinth/teams"))
+      .respond_with(response("modrinth_teams.json"))
+      .mount(&self.server)
+      .await;
+  }
+
+  async fn mount_curseforge(&self) {
+    Mock::given(method("POST"))
+      .and(path("/curseforge/mods"))
+      .and(header("x-api-key", "test-key"))
+      .respond_with(response("curseforge_mods.json"))
+      .mount(&self.server)
+      .await;
+  }
+
+  /// Pinned versions and files, plus the dependencies that aren't in the pack
+  async fn mount_dependencies(&self) {
+    Mock::given(method("GET"))
+      .and(path("/modrinth/versions"))
+      .respond_with(response("modrinth_versions.json"))
+      .mount(&self.server)
+      .await;
+
+    Mock::given(method("GET"))
+      .and(path("/modrinth/projects"))
+      .and(query_param("ids", r#"["P7dR8mSH","mOgUt4GM"]"#))
+      .respond_with(response("modrinth_dependencies.json"))
+      .with_priority(1)
+      .mount(&self.server)
+      .await;
+
+    Mock::given(method("POST"))
+      .and(path("/curseforge/mods/files"))
+      .and(header("x-api-key", "test-key"))
+      .respond_with(response("curseforge_files.json"))
+      .mount(&self.server)
+      .await;
+  }
+
+  /// `packwizml` pointed at the fake server, with everything else isolated in a temp dir,
+  /// [Fixture::run] also adds the CurseForge API key the server expects
+  fn command(&self) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_packwizml"));
+
+    command
+      .env_clear()
+      .env("HOME", self.dir.path())
+      .env("XDG_CONFIG_HOME", self.dir.path())
+      .env("APPDATA", self.dir.path())
+      .arg("--path")
+      .arg(&self.pack)
+      .arg("--cache")
+      .arg(self.dir.path().join("cache.json"))
+      .arg("--modrinth-api")
+      .arg(format!("{}/modrinth", self.server.uri()))
+      .arg("--curseforge-api")
+      .arg(format!("{}/curseforge", self.server.uri()))
+      .arg("--color-mode")
+      .arg("Never");
+
+    command
+  }
+
+  async fn run(&self, args: &[&str]) -> Output {
+    self
+      .command()
+      .args(["--curseforge-api-key", "test-key"])
+      .args(args)
+      .output()
+      .await
+      .unwrap()
+  }
+}
+
+fn stdout(output: &Output) -> String {
+  String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+#[tokio::test]
+async fn generates_modlist() {
+  let fixture = Fixture::new().await;
+  let output = fixture.run(&["--sort-by", "name"]).await;
+
+  assert!(output.status.success());
+  assert_eq!(stdout(&output), EXPECTED);
+}
+
+#[tokio::test]
+async fn writes_output_file() {
+  let fixture = Fixture::new().await;
+  let path = fixture.dir.path().join("MODLIST.md");
+  let output = fixture
+    .run(&["-s", "name", "-O", "-o", path.to_str().unwrap()])
+    .await;
+
+  assert!(output.status.success());
+  assert_eq!(std::fs::read_to_string(path).unwrap(), EXPECTED);
+}
+
+#[tokio::test]
+async fn prints_json() {
+  let fixture = Fixture::new().await;
+  let output = fixture.run(&["--json"]).await;
+  let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+  assert!(output.status.success());
+  assert_eq!(json["pack"]["name"], "Fixture Pack");
+  assert_eq!(json["projects"].as_array().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn uses_cache_on_second_run() {
+  let fixture = Fixture::empty().await;
+
+  Mock::given(path("/modrinth/projects"))
+    .respond_with(response("modrinth_projects.json"))
+    .expect(1)
+    .mount(&fixture.server)
+    .await;
+
+  Mock::given(path("/modrinth/teams"))
+    .respond_with(response("modrinth_teams.json"))
+    .expect(1)
+    .mount(&fixture.server)
+    .await;
+
+  Mock::given(path("/curseforge/mods"))
+    .respond_with(response("curseforge_mods.json"))
+    .expect(1)
+    .mount(&fixture.server)
+    .await;
+
+  let first = fixture.run(&["-s", "name"]).await;
+  let second = fixture.run(&["-s", "name"]).await;
+
+  assert_eq!(stdout(&first), EXPECTED);
+  assert_eq!(stdout(&second), EXPECTED);
+}
+
+/// A cached Modrinth project, as written by the cache
+fn cached_modrinth(id: &str, title: &str) -> serde_json::Value {
+  serde_json::json!({
+    "Modrinth": {
+      "id": id,
+      "slug": title.to_lowercase(),
+      "team": "team",
+      "icon_url": null,
+      "source_url": null,
+      "title": title,
+      "description": "",
+      "license": { "id": "MIT", "name": "MIT License", "url": null },
+    }
+  })
+}
+
+#[tokio::test]
+async fn keys_cache_entries_by_source() {
+  let fixture = Fixture::new().await;
+  let cache = fixture.dir.path().join("cache.json");
+
+  // Keyed without a source by older versions, and a Modrinth project that shares JEI's id
+  let seeded = serde_json::json!({
+    "AANobbMI": {
+      "hash": "1b0b3d3b8a7c0a0d7a2e4c6b3f1e9d8c7b6a5f40",
+      "project": cached_modrinth("AANobbMI", "Stale"),
+    },
+    "modrinth:238222": {
+      "version": 1,
+      "hash": "3d2d5f5dac9e2c2f9c4a6e8d5b3a1fbf9e8d7c62",
+      "project": cached_modrinth("238222", "Wrong Platform"),
+    },
+  });
+
+  std::fs::write(&cache, seeded.to_string()).unwrap();
+
+  let output = fixture.run(&["-s", "name"]).await;
+
+  assert_eq!(stdout(&output), EXPECTED);
+
+  let cache: serde_json::Value = serde_json::from_slice(&std::fs::read(&cache).unwrap()).unwrap();
+  let keys = cache.as_object().unwrap().keys().collect::<Vec<_>>();
+
+  assert_eq!(
+    keys,
+    [
+      "curseforge:238222",
+      "modrinth:238222",
+      "modrinth:AANobbMI",
+      "modrinth:gvQqBUqZ"
+    ]
+  );
+}
+
+#[tokio::test]
+async fn refetches_projects_from_older_caches() {
+  let fixture = Fixture::new().await;
+  let shared = fixture.dir.path().join("shared.json");
+  let mut sodium = cached_modrinth("AANobbMI", "Sodium");
+  let mut lithium = cached_modrinth("gvQqBUqZ", "Lithium");
+  let jei = serde_json::json!({
+    "CurseForge": {
+      "id": 238222,
+      "slug": "jei",
+      "name": "Just Enough Items (JEI)",
+      "summary": "",
+      "authors": [],
+      "logo": null,
+    }
+  });
+
+  // Written before projects had licenses or the distribution flag, and before versioning
+  sodium["Modrinth"]
+    .as_object_mut()
+    .unwrap()
+    .remove("license");
+  lithium["Modrinth"]
+    .as_object_mut()
+    .unwrap()
+    .remove("license");
+
+  let cache = serde_json::json!({
+    "modrinth:AANobbMI": { "hash": "1b0b3d3b8a7c0a0d7a2e4c6b3f1e9d8c7b6a5f40", "project": sodium },
+    "modrinth:gvQqBUqZ": { "hash": "2c1c4e4c9b8d1b1e8b3f5d7c4a2f0eae8d7c6b51", "project": lithium },
+    "curseforge:238222": { "hash": "3d2d5f5dac9e2c2f9c4a6e8d5b3a1fbf9e8d7c62", "project": jei },
+  });
+  let shared_cache = cache
+    .as_object()
+    .unwrap()
+    .iter()
+    .map(|(key, it)| {
+      (
+        key.clone(),
+        serde_json::json!({ "fetched": 0, "project": it["project"] }),
+      )
+    })
+    .collect::<serde_json::Map<_, _>>();
+
+  std::fs::write(fixture.dir.path().join("cache.json"), cache.to_string()).unwrap();
+  std::fs::write(&shared, serde_json::Value::from(shared_cache).to_string()).unwrap();
+
+  let output = fixture
+    .run(&["licenses", "--shared-cache", shared.to_str().unwrap()])
+    .await;
+
+  assert_eq!(output.status.code(), Some(1));
+  assert!(
+    stdout(&output).contains("LGPL-3.0-only (1)\n  Lithium\n"),
+    "{}",
+    stdout(&output)
+  );
+  assert!(
+    stdout(&output)
+      .contains("disallowed Just Enough Items (JEI) doesn't allow third-party distribution"),
+    "{}",
+    stdout(&output)
+  );
+
+  let shared: serde_json::Value = serde_json::from_slice(&std::fs::read(&shared).unwrap()).unwrap();
+
+  assert!(shared
+    .as_object()
+    .unwrap()
+    .values()
+    .all(|it| it["version"] == 1));
+}
+
+#[tokio::test]
+async fn shared_cache_is_used_across_packs() {
+  let fixture = Fixture::empty().await;
+  let shared = fixture.dir.path().join("shared").join("cache.json");
+  let shared = shared.to_str().unwrap();
+
+  Mock::given(path("/modrinth/projects"))
+    .respond_with(response("modrinth_projects.json"))
+    .expect(1)
+    .mount(&fixture.server)
+    .await;
+
+  Mock::given(path("/modrinth/teams"))
+    .respond_with(response("modrinth_teams.json"))
+    .expect(1)
+    .mount(&fixture.server)
+    .await;
+
+  Mock::given(path("/curseforge/mods"))
+    .respond_with(response("curseforge_mods.json"))
+    .expect(1)
+    .mount(&fixture.server)
+    .await;
+
+  let first = fixture.run(&["-s", "name", "--shared-cache", shared]).await;
+
+  // Another pack only has the shared cache to go on
+  std::fs::remove_file(fixture.dir.path().join("cache.json")).unwrap();
+
+  let second = fixture.run(&["-s", "name", "--shared-cache", shared]).await;
+
+  assert_eq!(stdout(&first), EXPECTED);
+  assert_eq!(stdout(&second), EXPECTED);
+}
+
+#[tokio::test]
+async fn parallel_runs_merge_into_shared_cache() {
+  let dir = TempDir::new().unwrap();
+  let shared = dir.path().join("shared.json");
+  let mut runs = Vec::new();
+
+  // Two packs with different mods, each saved twice at the same time
+  for (name, removed) in [("a", "lithium.pw.toml"), ("b", "sodium.pw.toml")] {
+    let pack = dir.path().join(name);
+
+    copy_dir(&fixtures().join("pack"), &pack);
+    std::fs::remove_file(pack.join("mods").join(removed)).unwrap();
+
+    for _ in 0..2 {
+      let mut fixture = Fixture::new().await;
+
+      fixture.pack = pack.clone();
+      runs.push(fixture);
+    }
+  }
+
+  let args = ["-v", "Off", "--shared-cache", shared.to_str().unwrap()];
+  let outputs = futures::future::join_all(runs.iter().map(|it| it.run(&args))).await;
+
+  assert!(outputs.iter().all(|it| it.status.success()));
+
+  let cache: serde_json::Value = serde_json::from_slice(&std::fs::read(&shared).unwrap()).unwrap();
+  let keys = cache.as_object().unwrap().keys().collect::<Vec<_>>();
+
+  assert_eq!(
+    keys,
+    [
+      "curseforge:238222",
+      "modrinth:AANobbMI",
+      "modrinth:gvQqBUqZ"
+    ]
+  );
+}
+
+#[tokio::test]
+async fn retries_when_rate_limited() {
+  let fixture = Fixture::empty().await;
+
+  Mock::given(path("/modrinth/projects"))
+    .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "0"))
+    .up_to_n_times(1)
+    .with_priority(1)
+    .mount(&fixture.server)
+    .await;
+
+  fixture.mount_modrinth().await;
+  fixture.mount_curseforge().await;
+
+  let output = fixture.run(&["-s", "name", "-v", "Off"]).await;
+
+  assert!(output.status.success());
+  assert_eq!(stdout(&output), EXPECTED);
+
+  // Waits longer than the backoff ever would fail straight away
+  let fixture = Fixture::empty().await;
+
+  Mock::given(path("/modrinth/projects"))
+    .respond_with(
+      ResponseTemplate::new(429)
+        .insert_header("x-ratelimit-remaining", "0")
+        .insert_header("x-ratelimit-reset", "3600"),
+    )
+    .expect(1)
+    .mount(&fixture.server)
+    .await;
+
+  fixture.mount_curseforge().await;
+
+  let start = std::time::Instant::now();
+  let output = fixture.run(&["-v", "Off"]).await;
+  let stderr = String::from_utf8_lossy(&output.stderr);
+
+  assert_eq!(output.status.code(), Some(7));
+  assert!(stderr.contains("asked to wait 3600s"), "{stderr}");
+  assert!(start.elapsed() < std::time::Duration::from_secs(10));
+}
+
+#[tokio::test]
+async fn retries_server_errors_with_backoff_until_max_retries() {
+  let fixture = Fixture::empty().await;
+
+  Mock::given(path("/modrinth/projects"))
+    .respond_with(ResponseTemplate::new(503))
+    .expect(2)
+    .mount(&fixture.server)
+    .await;
+
+  fixture.mount_curseforge().await;
+
+  let output = fixture.run(&["-v", "Off", "--max-retries", "1"]).await;
+
+  assert_eq!(output.status.code(), Some(7));
+}
+
+#[tokio::test]
+async fn requests_large_packs_in_chunks() {
+  let mut fixture = Fixture::empty().await;
+  let pack = fixture.dir.path().join("pack");
+  let mods = pack.join("mods");
+
+  std::fs::create_dir_all(&mods).unwrap();
+  std::fs::copy(
+    fixtures().join("pack").join("pack.toml"),
+    pack.join("pack.toml"),
+  )
+  .unwrap();
+  std::fs::write(pack.join("index.toml"), "hash-format = \"sha256\"\n").unwrap();
+
+  for i in 0..150 {
+    let metafile = format!(
+      "name = \"Mod {i}\"\nfilename = \"mod-{i}.jar\"\n\n\
+      [download]\nhash-format = \"sha1\"\nhash = \"{i}\"\n\n\
+      [update.modrinth]\nmod-id = \"id{i:03}\"\nversion = \"v{i}\"\n"
+    );
+
+    std::fs::write(mods.join(format!("mod-{i}.pw.toml")), metafile).unwrap();
+  }
+
+  fixture.pack = pack;
+
+  Mock::given(path("/modrinth/projects"))
+    .respond_with(ResponseTemplate::new(200).set_body_string("[]"))
+    .expect(2)
+    .mount(&fixture.server)
+    .await;
+
+  Mock::given(path("/modrinth/teams"))
+    .respond_with(ResponseTemplate::new(200).set_body_string("[]"))
+    .mount(&fixture.server)
+    .await;
+
+  let output = fixture.run(&["-v", "Off"]).await;
+
+  assert!(output.status.success());
+
+  let requests = fixture.server.received_requests().await.unwrap();
+  let mut ids = requests
+    .iter()
+    .filter(|it| it.url.path() == "/modrinth/projects")
+    .map(|it| {
+      let (_, ids) = it.url.query_pairs().find(|(key, _)| key == "ids").unwrap();
+
+      serde_json::from_str::<Vec<String>>(&ids).unwrap()
+    })
+    .collect::<Vec<_>>();
+
+  ids.sort_by_key(Vec::len);
+
+  assert_eq!(ids.iter().map(Vec::len).collect::<Vec<_>>(), [50, 100]);
+}
+
+#[tokio::test]
+async fn fetches_platforms_concurrently() {
+  let fixture = Fixture::empty().await;
+  let delay = std::time::Duration::from_millis(1500);
+
+  Mock::given(path("/modrinth/projects"))
+    .respond_with(response("modrinth_projects.json").set_delay(delay))
+    .mount(&fixture.server)
+    .await;
+
+  Mock::given(path("/modrinth/teams"))
+    .respond_with(response("modrinth_teams.json"))
+    .mount(&fixture.server)
+    .await;
+
+  Mock::given(path("/curseforge/mods"))
+    .respond_with(response("curseforge_mods.json").set_delay(delay))
+    .mount(&fixture.server)
+    .await;
+
+  let start = std::time::Instant::now();
+  let output = fixture.run(&["-s", "name"]).await;
+
+  assert_eq!(stdout(&output), EXPECTED);
+  // One after the other would take at least twice the delay
+  assert!(start.elapsed() < delay * 2, "{:?}", start.elapsed());
+}
+
+#[tokio::test]
+async fn sends_user_agent_with_contact() {
+  let fixture = Fixture::empty().await;
+  let user_agent = format!(
+    "Ricky12Awesome/packwiz-modlist/{} (pack@example.com)",
+    env!("CARGO_PKG_VERSION")
+  );
+
+  for (method_name, endpoint, body) in [
+    ("GET", "/modrinth/projects", "modrinth_projects.json"),
+    ("GET", "/modrinth/teams", "modrinth_teams.json"),
+    ("POST", "/curseforge/mods", "curseforge_mods.json"),
+  ] {
+    Mock::given(method(method_name))
+      .and(path(endpoint))
+      .and(header("user-agent", user_agent.as_str()))
+      .respond_with(response(body))
+      .expect(1)
+      .mount(&fixture.server)
+      .await;
+  }
+
+  let output = fixture
+    .run(&["-s", "name", "--contact", "pack@example.com"])
+    .await;
+
+  assert_eq!(stdout(&output), EXPECTED);
+}
+
+#[tokio::test]
+async fn reads_curseforge_key_from_env_and_user_config() {
+  let fixture = Fixture::new().await;
+
+  let output = fixture
+    .command()
+    .args(["-s", "name"])
+    .env("CF_API_KEY", "test-key")
+    .output()
+    .await
+    .unwrap();
+
+  assert_eq!(stdout(&output), EXPECTED);
+
+  let config = fixture.dir.path().join("packwiz-modlist");
+
+  std::fs::create_dir_all(&config).unwrap();
+  std::fs::write(
+    config.join("config.toml"),
+    "curseforge-api-key = \"test-key\"\n",
+  )
+  .unwrap();
+  std::fs::remove_file(fixture.dir.path().join("cache.json")).unwrap();
+
+  let output = fixture
+    .command()
+    .args(["-s", "name"])
+    .output()
+    .await
+    .unwrap();
+
+  assert_eq!(stdout(&output), EXPECTED);
+}
+
+#[tokio::test]
+async fn falls_back_to_metafile_without_curseforge_key() {
+  let fixture = Fixture::empty().await;
+
+  fixture.mount_modrinth().await;
+
+  // An empty key disables CurseForge, even if the binary was built with one
+  let output = fixture
+    .command()
+    .args(["-s", "name", "--curseforge-api-key", ""])
+    .output()
+    .await
+    .unwrap();
+  let stderr = String::from_utf8_lossy(&output.stderr);
+
+  assert!(output.status.success());
+  assert!(stdout(&output)
+    .starts_with("- [Just Enough Items (JEI)](https://www.curseforge.com/projects/238222) - \n"));
+  assert!(
+    stderr.contains("Set one with '--curseforge-api-key', the CF_API_KEY environment variable"),
+    "{stderr}"
+  );
+}
+
+#[tokio::test]
+async fn applies_config_file() {
+  let fixture = Fixture::new().await;
+  let config = fixture.dir.path().join("packwizml.toml");
+
+  std::fs::write(&config, "sort-by = \"name\"\nformat = \"{SLUG}\\n\"\n").unwrap();
+
+  let config = config.to_str().unwrap();
+  let output = fixture.run(&["--config", config]).await;
+
+  assert!(output.status.success());
+  assert_eq!(stdout(&output), "jei\nlithium\nsodium\n");
+
+  let output = fixture.run(&["--config", config, "-f", "{ID}\n"]).await;
+
+  assert!(output.status.success());
+  assert_eq!(stdout(&output), "238222\ngvQqBUqZ\nAANobbMI\n");
+}
+
+#[tokio::test]
+async fn writes_every_configured_output() {
+  let fixture = Fixture::new().await;
+  let dir = fixture.dir.path();
+  let config = dir.join("packwizml.toml");
+
+  std::fs::write(
+    &config,
+    format!(
+      r#"
+sort-by = "name"
+output-custom = true
+
+[outputs.markdown]
+path = '{0}/MODLIST.md'
+
+[outputs.description]
+path = '{0}/description.html'
+preset = "html"
+filter = {{ sources = ["modrinth"], exclude = ["lithium"] }}
+
+[outputs.launcher]
+path = '{0}/modlist.json'
+preset = "json"
+filter = {{ sides = ["both"] }}
+"#,
+      dir.display()
+    ),
+  )
+  .unwrap();
+
+  let output = fixture.run(&["--config", config.to_str().unwrap()]).await;
+  let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+  let json: serde_json::Value = serde_json::from_str(&read("modlist.json")).unwrap();
+
+  assert!(output.status.success());
+  assert!(stdout(&output).is_empty());
+  assert_eq!(read("MODLIST.md"), EXPECTED);
+  assert_eq!(
+    read("description.html"),
+    "<li><a href=\"https://modrinth.com/mod/AANobbMI\">Sodium</a> - \
+    The fastest and most compatible rendering optimization mod for Minecraft</li>\n"
+  );
+  assert_eq!(json["projects"][0]["CurseForge"]["slug"], "jei");
+  assert_eq!(json["projects"].as_array().unwrap().len(), 2);
+
+  // A typo in a side fails instead of leaving the output empty
+  let toml = read("packwizml.toml").replace(r#"["both"]"#, r#"["clinet"]"#);
+
+  std::fs::write(&config, toml).unwrap();
+
+  let output = fixture.run(&["--config", config.to_str().unwrap()]).await;
+
+  assert_eq!(output.status.code(), Some(5));
+  assert!(
+    String::from_utf8_lossy(&output.stderr).contains("clinet"),
+    "{}",
+    String::from_utf8_lossy(&output.stderr)
+  );
+}
+
+#[tokio::test]
+async fn escapes_values_in_html_preset() {
+  let fixture = Fixture::empty().await;
+  let dir = fixture.dir.path();
+  let config = dir.join("packwizml.toml");
+  let projects = std::fs::read_to_string(fixtures().join("responses/modrinth_projects.json"))
+    .unwrap()
+    .replace(
+      "No-compromises",
+      "<script>alert('x')</script> & \\\"no-compromises\\\"",
+    );
+
+  Mock::given(path("/modrinth/projects"))
+    .respond_with(ResponseTemplate::new(200).set_body_raw(projects, "application/json"))
+    .mount(&fixture.server)
+    .await;
+
+  Mock::given(path("/modrinth/teams"))
+    .respond_with(response("modrinth_teams.json"))
+    .mount(&fixture.server)
+    .await;
+
+  fixture.mount_curseforge().await;
+
+  std::fs::write(
+    &config,
+    format!(
+      r#"
+output-custom = true
+
+[outputs.description]
+path = '{}/description.html'
+preset = "html"
+filter = {{ exclude = ["sodium", "jei"] }}
+"#,
+      dir.display()
+    ),
+  )
+  .unwrap();
+
+  let output = fixture.run(&["--config", config.to_str().unwrap()]).await;
+
+  assert!(output.status.success());
+  assert_eq!(
+    std::fs::read_to_string(dir.join("description.html")).unwrap(),
+    "<li><a href=\"https://modrinth.com/mod/gvQqBUqZ\">Lithium</a> - \
+    &lt;script&gt;alert(&#39;x&#39;)&lt;/script&gt; &amp; &quot;no-compromises&quot; \
+    game logic optimization mod</li>\n"
+  );
+}
+
+#[tokio::test]
+async fn generate_subcommand_accepts_options_after_it() {
+  let fixture = Fixture::new().await;
+  let output = fixture.run(&["generate", "--sort-by", "name"]).await;
+
+  assert!(output.status.success());
+  assert_eq!(stdout(&output), EXPECTED);
+}
+
+#[tokio::test]
+async fn prints_completions_and_man_page() {
+  let fixture = Fixture::empty().await;
+
+  for shell in ["bash", "zsh", "fish", "powershell", "elvish"] {
+    let output = fixture.run(&["completions", shell]).await;
+
+    assert!(output.status.success(), "{shell}");
+    assert!(stdout(&output).contains("packwizml"), "{shell}");
+  }
+
+  let output = fixture.run(&["man"]).await;
+
+  assert!(output.status.success());
+  assert!(stdout(&output).starts_with(".ie"));
+
+  // Neither needs the config, so a broken one doesn't matter
+  let config = fixture.dir.path().join("packwizml.toml");
+  let config = config.to_str().unwrap();
+
+  std::fs::write(config, "sort-by = [\n").unwrap();
+
+  for args in [vec!["completions", "bash"], vec!["man"]] {
+    let output = fixture
+      .run(&[&["--config", config], &args[..]].concat())
+      .await;
+
+    assert!(output.status.success(), "{args:?}");
+  }
+
+  let output = fixture.run(&["--config", config]).await;
+
+  assert_eq!(output.status.code(), Some(5));
+}
+
+fn copy_dir(from: &Path, to: &Path) {
+  std::fs::create_dir_all(to).unwrap();
+
+  for entry in std::fs::read_dir(from).unwrap() {
+    let entry = entry.unwrap();
+    let to = to.join(entry.file_name());
+
+    if entry.file_type().unwrap().is_dir() {
+      copy_dir(&entry.path(), &to);
+    } else {
+      std::fs::copy(entry.path(), to).unwrap();
+    }
+  }
+}
+
+/// Waits until the file at `path` contains `expected`
+async fn wait_for(path: &Path, expected: &str) -> bool {
+  for _ in 0..100 {
+    if std::fs::read_to_string(path).is_ok_and(|it| it == expected) {
+      return true;
+    }
+
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+  }
+
+  false
+}
+
+#[tokio::test]
+async fn watch_regenerates_when_mods_change() {
+  let mut fixture = Fixture::empty().await;
+  let pack = fixture.dir.path().join("pack");
+  let output = pack.join("MODLIST.md");
+
+  copy_dir(&fixtures().join("pack"), &pack);
+  fixture.pack = pack.clone();
+
+  // Everything is fetched once, the regeneration comes from the cache
+  Mock::given(path("/modrinth/projects"))
+    .respond_with(response("modrinth_projects.json"))
+    .expect(1)
+    .mount(&fixture.server)
+    .await;
+
+  Mock::given(path("/modrinth/teams"))
+    .respond_with(response("modrinth_teams.json"))
+    .expect(1)
+    .mount(&fixture.server)
+    .await;
+
+  fixture.mount_curseforge().await;
+
+  let mut child = fixture
+    .command()
+    .args([
+      "--curseforge-api-key",
+      "test-key",
+      "-s",
+      "name",
+      "-o",
+      "MODLIST.md",
+    ])
+    .arg("--watch")
+    .kill_on_drop(true)
+    .spawn()
+    .unwrap();
+
+  assert!(wait_for(&output, EXPECTED).await);
+
+  std::fs::remove_file(pack.join("mods").join("jei.pw.toml")).unwrap();
+
+  let expected = EXPECTED
+    .lines()
+    .skip(1)
+    .map(|it| format!("{it}\n"))
+    .collect::<String>();
+
+  assert!(wait_for(&output, &expected).await);
+
+  child.kill().await.unwrap();
+}
+
+#[tokio::test]
+async fn watch_keeps_existing_output_without_force() {
+  use tokio::io::{AsyncBufReadExt, BufReader};
+
+  let mut fixture = Fixture::new().await;
+  let pack = fixture.dir.path().join("pack");
+  let output = pack.join("MODLIST.md");
+
+  copy_dir(&fixtures().join("pack"), &pack);
+  std::fs::write(&output, "hand-written\n").unwrap();
+  fixture.pack = pack.clone();
+
+  let mut child = fixture
+    .command()
+    .args(["--curseforge-api-key", "test-key", "-o", "MODLIST.md"])
+    .args(["--watch", "-v", "Info"])
+    .stderr(std::process::Stdio::piped())
+    .kill_on_drop(true)
+    .spawn()
+    .unwrap();
+
+  let mut lines = BufReader::new(child.stderr.take().unwrap()).lines();
+  let mut refused = 0;
+
+  // Refused once on the first run, then again after the change
+  let wait = async {
+    while let Some(line) = lines.next_line().await.unwrap() {
+      if line.contains("Watching") {
+        std::fs::remove_file(pack.join("mods").join("jei.pw.toml")).unwrap();
+      }
+
+      if line.contains("already exists") {
+        refused += 1;
+
+        if refused == 2 {
+          break;
+        }
+      }
+    }
+  };
+
+  tokio::time::timeout(std::time::Duration::from_secs(10), wait)
+    .await
+    .unwrap();
+
+  assert_eq!(std::fs::read_to_string(&output).unwrap(), "hand-written\n");
+
+  child.kill().await.unwrap();
+}
+
+#[tokio::test]
+async fn check_fails_with_diff_when_output_is_outdated() {
+  let fixture = Fixture::new().await;
+  let path = fixture.dir.path().join("MODLIST.md");
+  let path = path.to_str().unwrap();
+
+  std::fs::write(path, EXPECTED.replace("Lithium", "Phosphor")).unwrap();
+
+  let output = fixture
+    .run(&["check", "-s", "name", "-O", "-o", path])
+    .await;
+
+  assert_eq!(output.status.code(), Some(1));
+  assert!(stdout(&output).contains("-- [Phosphor]"));
+  assert!(stdout(&output).contains("+- [Lithium]"));
+
+  std::fs::write(path, EXPECTED).unwrap();
+
+  let output = fixture
+    .run(&["--check", "-s", "name", "-O", "-o", path])
+    .await;
+
+  assert!(output.status.success());
+  assert!(stdout(&output).is_empty());
+  assert_eq!(std::fs::read_to_string(path).unwrap(), EXPECTED);
+  assert!(!fixture.dir.path().join("cache.json").exists());
+}
+
+#[tokio::test]
+async fn injects_between_markers() {
+  let fixture = Fixture::new().await;
+  let path = fixture.dir.path().join("README.md");
+  let path = path.to_str().unwrap();
+  let readme = |list: &str| {
+    format!("# Pack\n\n<!-- packwizml:start -->\n{list}<!-- packwizml:end -->\n\nThanks!\n")
+  };
+
+  std::fs::write(path, readme("- old\n")).unwrap();
+
+  let output = fixture
+    .run(&["-s", "name", "-O", "-o", path, "--inject"])
+    .await;
+
+  assert!(output.status.success());
+  assert_eq!(std::fs::read_to_string(path).unwrap(), readme(EXPECTED));
+
+  let output = fixture
+    .run(&["--check", "-s", "name", "-O", "-o", path, "-i"])
+    .await;
+
+  assert!(output.status.success());
+
+  std::fs::write(path, "# Pack\n").unwrap();
+
+  let output = fixture.run(&["-s", "name", "-O", "-o", path, "-i"]).await;
+
+  assert!(!stdout(&output).contains("- [Sodium]"));
+  assert_eq!(std::fs::read_to_string(path).unwrap(), "# Pack\n");
+}
+
+#[tokio::test]
+async fn exits_with_error_code_on_failure() {
+  let mut fixture = Fixture::empty().await;
+
+  fixture.pack = fixture.dir.path().join("missing");
+
+  let output = fixture.run(&["-v", "Off"]).await;
+  let stderr = String::from_utf8_lossy(&output.stderr);
+
+  assert_eq!(output.status.code(), Some(3));
+  assert!(stdout(&output).is_empty());
+  assert!(stderr.contains("error: Validation:"), "{stderr}");
+
+  let output = fixture.run(&["--json"]).await;
+  let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+  assert_eq!(output.status.code(), Some(3));
+  assert_eq!(json["kind"], "Validation");
+  assert_eq!(json["code"], 3);
+}
+
+#[tokio::test]
+async fn reports_broken_metafile_and_skips_it_with_keep_going() {
+  let mut fixture = Fixture::new().await;
+  let pack = fixture.dir.path().join("pack");
+
+  copy_dir(&fixtures().join("pack"), &pack);
+  std::fs::write(
+    pack.join("mods").join("broken.pw.toml"),
+    "name = \"Broken\"\nside = \n",
+  )
+  .unwrap();
+  fixture.pack = pack;
+
+  let output = fixture.run(&["-s", "name"]).await;
+  let stderr = String::from_utf8_lossy(&output.stderr);
+
+  assert_eq!(output.status.code(), Some(5));
+  assert!(stderr.contains("broken.pw.toml:2:8: "), "{stderr}");
+
+  let output = fixture.run(&["-s", "name", "--keep-going"]).await;
+  let stderr = String::from_utf8_lossy(&output.stderr);
+
+  assert!(output.status.success());
+  assert_eq!(stdout(&output), EXPECTED);
+  assert!(stderr.contains("Skipped 1 metafiles"), "{stderr}");
+}
+
+#[tokio::test]
+async fn reports_projects_the_api_did_not_return() {
+  let mut fixture = Fixture::new().await;
+  let pack = fixture.dir.path().join("pack");
+  let mods = pack.join("mods");
+
+  copy_dir(&fixtures().join("pack"), &pack);
+
+  let deleted = std::fs::read_to_string(mods.join("sodium.pw.toml"))
+    .unwrap()
+    .replace("Sodium", "Deleted")
+    .replace("AANobbMI", "deLeTeD1");
+
+  std::fs::write(mods.join("deleted.pw.toml"), deleted).unwrap();
+  fixture.pack = pack;
+
+  let output = fixture.run(&["-s", "name"]).await;
+  let stderr = String::from_utf8_lossy(&output.stderr);
+
+  assert!(output.status.success());
+  assert_eq!(stdout(&output), EXPECTED);
+  assert!(
+    stderr.contains("Deleted (modrinth:deLeTeD1) was not returned"),
+    "{stderr}"
+  );
+
+  let output = fixture.run(&["-s", "name", "--metafile-fallback"]).await;
+
+  assert!(output.status.success());
+  assert!(stdout(&output).starts_with("- [Deleted](https://modrinth.com/mod/deLeTeD1) - \n"));
+
+  let output = fixture.run(&["-s", "name", "--strict"]).await;
+
+  assert_eq!(output.status.code(), Some(3));
+  assert!(stdout(&output).is_empty());
+}
+
+#[tokio::test]
+async fn reports_api_errors_with_status_and_hint() {
+  let fixture = Fixture::empty().await;
+
+  fixture.mount_modrinth().await;
+
+  Mock::given(path("/curseforge/mods"))
+    .respond_with(ResponseTemplate::new(403).set_body_string("Forbidden"))
+    .mount(&fixture.server)
+    .await;
+
+  let output = fixture.run(&[]).await;
+  let stderr = String::from_utf8_lossy(&output.stderr);
+
+  assert_eq!(output.status.code(), Some(7));
+  assert!(
+    stderr.contains("CurseForge /curseforge/mods returned 403 Forbidden"),
+    "{stderr}"
+  );
+  assert!(stderr.contains("response: Forbidden"), "{stderr}");
+  assert!(
+    stderr.contains("hint: check your CurseForge API key"),
+    "{stderr}"
+  );
+}
+
+#[tokio::test]
+async fn validates_pack_against_spec() {
+  let mut fixture = Fixture::empty().await;
+  let pack = fixture.dir.path().join("pack");
+  let mods = pack.join("mods");
+
+  copy_dir(&fixtures().join("pack"), &pack);
+  fixture.pack = pack.clone();
+
+  let output = fixture.run(&["validate"]).await;
+
+  assert!(output.status.success());
+  assert!(stdout(&output).is_empty());
+
+  std::fs::write(
+    mods.join("bad-hash.pw.toml"),
+    "name = \"Bad\"\nfilename = \"bad.jar\"\n\n[download]\nurl = \"https://example.com/bad.jar\"\nhash-format = \"sha1\"\nhash = \"nope\"\n",
+  )
+  .unwrap();
+
+  std::fs::write(
+    mods.join("bad-side.pw.toml"),
+    "name = \"Bad\"\nfilename = \"bad.jar\"\nside = \"everywhere\"\n",
+  )
+  .unwrap();
+
+  let output = fixture.run(&["validate"]).await;
+  let stdout = stdout(&output);
+
+  assert_eq!(output.status.code(), Some(1));
+  assert!(
+    stdout.contains("bad-hash.pw.toml:7:8: 'nope' is not a valid sha1 hash"),
+    "{stdout}"
+  );
+  assert!(
+    stdout.contains("bad-side.pw.toml:3:8: unknown variant `everywhere`"),
+    "{stdout}"
+  );
+}
+
+#[tokio::test]
+async fn accepts_minimal_pack_without_update_sections() {
+  let mut fixture = Fixture::new().await;
+  let pack = fixture.dir.path().join("pack");
+
+  copy_dir(&fixtures().join("pack"), &pack);
+  fixture.pack = pack.clone();
+
+  std::fs::write(
+    pack.join("pack.toml"),
+    "name = \"Minimal\"\n\n[index]\nfile = \"index.toml\"\nhash-format = \"sha256\"\n\n\
+    [versions]\nminecraft = \"1.20.4\"\nquilt = \"0.25.0\"\n",
+  )
+  .unwrap();
+
+  std::fs::write(
+    pack.join("mods").join("url.pw.toml"),
+    "name = \"From Url\"\nfilename = \"url.jar\"\n\n[download]\nurl = \"https://example.com/url.jar\"\n\
+    hash-format = \"murmur2\"\nhash = \"12345\"\n",
+  )
+  .unwrap();
+
+  let output = fixture.run(&["-s", "name"]).await;
+
+  assert!(output.status.success());
+  assert_eq!(
+    stdout(&output),
+    format!("- [From Url](https://example.com/url.jar) - \n{EXPECTED}")
+  );
+
+  // 'pack-format' is optional too
+  let output = fixture.run(&["validate"]).await;
+
+  assert!(output.status.success(), "{}", stdout(&output));
+  assert!(stdout(&output).is_empty());
+}
+
+#[tokio::test]
+async fn uses_pack_loader() {
+  let mut fixture = Fixture::new().await;
+  let pack = fixture.dir.path().join("pack");
+
+  copy_dir(&fixtures().join("pack"), &pack);
+  fixture.pack = pack.clone();
+
+  let output = fixture
+    .run(&["-s", "name", "-f", "{NAME} {LOADER} {LOADER_VERSION}\n"])
+    .await;
+  let stderr = String::from_utf8_lossy(&output.stderr);
+
+  assert!(output.status.success());
+  assert!(stdout(&output).starts_with("Just Enough Items (JEI) Fabric 0.15.7\n"));
+  assert!(!stderr.contains("doesn't support"), "{stderr}");
+
+  let toml = std::fs::read_to_string(pack.join("pack.toml"))
+    .unwrap()
+    .replace("fabric = \"0.15.7\"", "neoforge = \"20.4.80\"");
+
+  std::fs::write(pack.join("pack.toml"), toml).unwrap();
+
+  let output = fixture
+    .run(&["-s", "name", "-f", "{LOADER} {LOADER_VERSION}\n"])
+    .await;
+  let stderr = String::from_utf8_lossy(&output.stderr);
+
+  assert!(stdout(&output).starts_with("NeoForge 20.4.80\n"));
+  assert!(
+    stderr.contains("Sodium doesn't support NeoForge, only fabric, quilt"),
+    "{stderr}"
+  );
+
+  // The pinned versions are checked instead when they're fetched
+  fixture.mount_dependencies().await;
+
+  let output = fixture.run(&["--dependencies"]).await;
+  let stderr = String::from_utf8_lossy(&output.stderr);
+
+  assert!(output.status.success());
+  assert!(
+    stderr.contains("Sodium's pinned version doesn't support NeoForge, only fabric, quilt"),
+    "{stderr}"
+  );
+  assert!(
+    stderr.contains("Just Enough Items (JEI)'s pinned file doesn't support NeoForge, only fabric"),
+    "{stderr}"
+  );
+  assert!(!stderr.contains("Sodium doesn't support"), "{stderr}");
+}
+
+#[tokio::test]
+async fn verifies_jars_against_metafile_hashes() {
+  let mut fixture = Fixture::empty().await;
+  let pack = fixture.dir.path().join("pack");
+  let jars = fixture.dir.path().join("jars");
+  let sodium = pack.join("mods").join("sodium.pw.toml");
+
+  copy_dir(&fixtures().join("pack"), &pack);
+  fixture.pack = pack.clone();
+
+  let toml = std::fs::read_to_string(&sodium).unwrap().replace(
+    "1b0b3d3b8a7c0a0d7a2e4c6b3f1e9d8c7b6a5f40",
+    "6f07dc1d0a287f925d7672e6884d85ceb3f339ed",
+  );
+
+  std::fs::write(sodium, toml).unwrap();
+  std::fs::create_dir(&jars).unwrap();
+  std::fs::write(jars.join("sodium-fabric-0.5.8+mc1.20.4.jar"), "sodium").unwrap();
+  std::fs::write(jars.join("lithium-fabric-mc1.20.4-0.12.1.jar"), "tampered").unwrap();
+  std::fs::write(jars.join("extra.jar"), "extra").unwrap();
+
+  let output = fixture.run(&["verify", jars.to_str().unwrap()]).await;
+  let stdout = stdout(&output);
+
+  assert_eq!(output.status.code(), Some(1));
+  assert!(
+    stdout.contains("missing  jei-1.20.4-fabric-17.3.0.49.jar"),
+    "{stdout}"
+  );
+  assert!(
+    stdout.contains("mismatch lithium-fabric-mc1.20.4-0.12.1.jar, expected sha1"),
+    "{stdout}"
+  );
+  assert!(stdout.contains("extra    extra.jar"), "{stdout}");
+  assert!(!stdout.contains("sodium"), "{stdout}");
+
+  std::fs::remove_file(jars.join("extra.jar")).unwrap();
+  std::fs::remove_file(jars.join("lithium-fabric-mc1.20.4-0.12.1.jar")).unwrap();
+  std::fs::remove_file(pack.join("mods").join("lithium.pw.toml")).unwrap();
+  std::fs::remove_file(pack.join("mods").join("jei.pw.toml")).unwrap();
+
+  let output = fixture
+    .run(&["verify", jars.to_str().unwrap(), "--side", "client"])
+    .await;
+
+  assert!(output.status.success());
+}
+
+#[tokio::test]
+async fn verifies_curseforge_murmur2_fingerprints() {
+  let mut fixture = Fixture::empty().await;
+  let pack = fixture.dir.path().join("pack");
+  let jars = fixture.dir.path().join("jars");
+  let jei = pack.join("mods").join("jei.pw.toml");
+  let jar = "jei-1.20.4-fabric-17.3.0.49.jar";
+
+  copy_dir(&fixtures().join("pack"), &pack);
+  fixture.pack = pack.clone();
+
+  // Fingerprint of the fixture jar from the reference MurmurHash2 in C,
+  // seed 1 with tab, newline, carriage return and space bytes skipped like CurseForge does
+  let toml = std::fs::read_to_string(&jei).unwrap().replace(
+    "hash-format = \"sha1\"\nhash = \"3d2d5f5dac9e2c2f9c4a6e8d5b3a1fbf9e8d7c62\"",
+    "hash-format = \"murmur2\"\nhash = \"930531653\"",
+  );
+
+  std::fs::write(&jei, toml).unwrap();
+  std::fs::remove_file(pack.join("mods").join("sodium.pw.toml")).unwrap();
+  std::fs::remove_file(pack.join("mods").join("lithium.pw.toml")).unwrap();
+  std::fs::create_dir(&jars).unwrap();
+  std::fs::copy(fixtures().join("jars").join(jar), jars.join(jar)).unwrap();
+
+  let output = fixture.run(&["verify", jars.to_str().unwrap()]).await;
+
+  assert!(output.status.success(), "{}", stdout(&output));
+
+  let toml = std::fs::read_to_string(&jei)
+    .unwrap()
+    .replace("930531653", "930531654");
+
+  std::fs::write(&jei, toml).unwrap();
+
+  let output = fixture.run(&["verify", jars.to_str().unwrap()]).await;
+  let stdout = stdout(&output);
+
+  assert_eq!(output.status.code(), Some(1));
+  assert!(
+    stdout.contains(&format!("mismatch {jar}, expected murmur2")),
+    "{stdout}"
+  );
+}
+
+fn write_jar(path: &Path, entry: &str, content: &str) {
+  use std::io::Write;
+
+  let mut zip = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+
+  zip
+    .start_file(entry, zip::write::SimpleFileOptions::default())
+    .unwrap();
+  zip.write_all(content.as_bytes()).unwrap();
+  zip.finish().unwrap();
+}
+
+#[tokio::test]
+async fn compares_instance_with_pack() {
+  let mut fixture = Fixture::empty().await;
+  let pack = fixture.dir.path().join("pack");
+  let instance = fixture.dir.path().join("instance");
+  let sodium = pack.join("mods").join("sodium.pw.toml");
+
+  copy_dir(&fixtures().join("pack"), &pack);
+  std::fs::create_dir(&instance).unwrap();
+  fixture.pack = pack.clone();
+
+  let toml = std::fs::read_to_string(&sodium).unwrap().replace(
+    "1b0b3d3b8a7c0a0d7a2e4c6b3f1e9d8c7b6a5f40",
+    "6f07dc1d0a287f925d7672e6884d85ceb3f339ed",
+  );
+
+  std::fs::write(sodium, toml).unwrap();
+  std::fs::write(instance.join("renamed-sodium.jar"), "sodium").unwrap();
+
+  write_jar(
+    &instance.join("lithium-old.jar"),
+    "fabric.mod.json",
+    r#"{ "id": "lithium", "version": "0.11.2" }"#,
+  );
+
+  write_jar(
+    &instance.join("worldedit.jar"),
+    "META-INF/mods.toml",
+    "[[mods]]\nmodId = \"worldedit\"\nversion = \"${file.jarVersion}\"\n",
+  );
+
+  let output = fixture.run(&["compare", instance.to_str().unwrap()]).await;
+  let stdout = stdout(&output);
+
+  assert_eq!(output.status.code(), Some(1));
+  assert!(
+    stdout.contains("missing   Just Enough Items (JEI)"),
+    "{stdout}"
+  );
+  assert!(
+    stdout.contains(
+      "different Lithium, pack has lithium-fabric-mc1.20.4-0.12.1.jar, \
+      installed lithium-old.jar (0.11.2)"
+    ),
+    "{stdout}"
+  );
+  assert!(stdout.contains("extra     worldedit.jar"), "{stdout}");
+  assert!(!stdout.contains("sodium"), "{stdout}");
+}
+
+#[tokio::test]
+async fn lists_dependencies_and_libraries() {
+  let fixture = Fixture::new().await;
+
+  fixture.mount_dependencies().await;
+
+  let output = fixture
+    .run(&[
+      "-s",
+      "name",
+      "--dependencies",
+      "-f",
+      "{NAME} [{DEPENDENCIES}] [{REQUIRED_BY}]\n",
+    ])
+    .await;
+  let stderr = String::from_utf8_lossy(&output.stderr);
+
+  assert!(output.status.success());
+  assert_eq!(
+    stdout(&output),
+    "Just Enough Items (JEI) [] []\n\
+    Lithium [Sodium] []\n\
+    Sodium [Fabric API] [Lithium]\n"
+  );
+  assert!(
+    stderr.contains("Sodium requires Fabric API, which isn't in the pack"),
+    "{stderr}"
+  );
+  assert!(!stderr.contains("doesn't support"), "{stderr}");
+
+  // Sodium has no categories in the fixture, so being required makes it a library
+  let output = fixture
+    .run(&["-s", "name", "-r", "--dependencies", "-f", "{NAME}\n"])
+    .await;
+
+  assert_eq!(
+    stdout(&output),
+    "Lithium\nJust Enough Items (JEI)\nSodium\n"
+  );
+}
+
+#[tokio::test]
+async fn keeps_mods_listed_as_something_else_out_of_libraries() {
+  let fixture = Fixture::empty().await;
+  let mut projects: serde_json::Value = serde_json::from_str(
+    &std::fs::read_to_string(fixtures().join("responses").join("modrinth_projects.json")).unwrap(),
+  )
+  .unwrap();
+
+  projects[0]["categories"] = serde_json::json!(["optimization"]);
+
+  Mock::given(method("GET"))
+    .and(path("/modrinth/projects"))
+    .and(|request: &wiremock::Request| request.url.as_str().contains("gvQqBUqZ"))
+    .respond_with(ResponseTemplate::new(200).set_body_json(projects))
+    .with_priority(1)
+    .mount(&fixture.server)
+    .await;
+
+  fixture.mount_modrinth().await;
+  fixture.mount_curseforge().await;
+  fixture.mount_dependencies().await;
+
+  let output = fixture
+    .run(&[
+      "-s",
+      "name",
+      "-r",
+      "--dependencies",
+      "-f",
+      "{NAME} [{REQUIRED_BY}]\n",
+    ])
+    .await;
+
+  assert!(output.status.success());
+  assert_eq!(
+    stdout(&output),
+    "Sodium [Lithium]\nLithium []\nJust Enough Items (JEI) []\n"
+  );
+}
+
+#[tokio::test]
+async fn fetches_dependencies_alongside_projects() {
+  let fixture = Fixture::empty().await;
+  let delay = std::time::Duration::from_millis(1500);
+
+  Mock::given(path("/modrinth/projects"))
+    .and(query_param("ids", r#"["P7dR8mSH","mOgUt4GM"]"#))
+    .respond_with(response("modrinth_dependencies.json"))
+    .with_priority(1)
+    .mount(&fixture.server)
+    .await;
+
+  Mock::given(path("/modrinth/projects"))
+    .respond_with(response("modrinth_projects.json").set_delay(delay))
+    .mount(&fixture.server)
+    .await;
+
+  Mock::given(path("/modrinth/versions"))
+    .respond_with(response("modrinth_versions.json").set_delay(delay))
+    .mount(&fixture.server)
+    .await;
+
+  // Dependencies only need a title and slug, so only the pack's teams are fetched
+  Mock::given(path("/modrinth/teams"))
+    .respond_with(response("modrinth_teams.json"))
+    .expect(1)
+    .mount(&fixture.server)
+    .await;
+
+  Mock::given(path("/curseforge/mods"))
+    .respond_with(response("curseforge_mods.json"))
+    .mount(&fixture.server)
+    .await;
+
+  Mock::given(path("/curseforge/mods/files"))
+    .respond_with(response("curseforge_files.json"))
+    .mount(&fixture.server)
+    .await;
+
+  let start = std::time::Instant::now();
+  let output = fixture
+    .run(&[
+      "-s",
+      "name",
+      "--dependencies",
+      "-f",
+      "{NAME} [{DEPENDENCIES}]\n",
+    ])
+    .await;
+
+  assert!(output.status.success());
+  assert_eq!(
+    stdout(&output),
+    "Just Enough Items (JEI) []\nLithium [Sodium]\nSodium [Fabric API]\n"
+  );
+  // The versions after the projects would take at least twice the delay
+  assert!(start.elapsed() < delay * 2, "{:?}", start.elapsed());
+}
+
+#[tokio::test]
+async fn prints_dependency_graph() {
+  let fixture = Fixture::new().await;
+
+  fixture.mount_dependencies().await;
+
+  let output = fixture.run(&["graph"]).await;
+  let dot = stdout(&output);
+
+  assert!(output.status.success());
+  assert!(
+    dot.contains(r#""modrinth:P7dR8mSH" [label="Fabric API", style=dashed];"#),
+    "{dot}"
+  );
+  assert!(
+    dot.contains(r#""modrinth:gvQqBUqZ" -> "modrinth:AANobbMI";"#),
+    "{dot}"
+  );
+
+  let output = fixture.run(&["graph", "--format", "mermaid"]).await;
+
+  assert!(output.status.success());
+  assert_eq!(
+    stdout(&output),
+    "graph LR\n  \
+    classDef missing stroke-dasharray: 5 5\n  \
+    n0[\"Fabric API\"]:::missing\n  \
+    n1[\"Just Enough Items (JEI)\"]\n  \
+    n2[\"Lithium\"]\n  \
+    n3[\"Mod Menu\"]:::missing\n  \
+    n4[\"Sodium\"]\n  \
+    n4 --> n0\n  \
+    n2 --> n4\n  \
+    n2 -.->|optional| n3\n"
+  );
+
+  let output = fixture.run(&["graph", "--format", "svg"]).await;
+
+  assert_eq!(output.status.code(), Some(2));
+
+  // Before the command works too, and a modlist format from the config is left out
+  std::fs::write(
+    fixture.dir.path().join("packwizml.toml"),
+    "format = \"{NAME}\\n\"\n",
+  )
+  .unwrap();
+
+  let config = fixture.dir.path().join("packwizml.toml");
+  let output = fixture
+    .run(&["--config", config.to_str().unwrap(), "graph"])
+    .await;
+
+  assert!(
+    stdout(&output).starts_with("digraph"),
+    "{}",
+    stdout(&output)
+  );
+
+  let output = fixture.run(&["-f", "mermaid", "graph"]).await;
+
+  assert!(
+    stdout(&output).starts_with("graph LR\n"),
+    "{}",
+    stdout(&output)
+  );
+}
+
+#[tokio::test]
+async fn reports_licenses() {
+  let fixture = Fixture::new().await;
+  let output = fixture.run(&["licenses"]).await;
+
+  assert_eq!(output.status.code(), Some(1));
+  assert_eq!(
+    stdout(&output),
+    "LGPL-3.0-only (1)\n  \
+    Lithium\n\
+    Polyform Shield License 1.0.0 (1)\n  \
+    Sodium\n\
+    Unknown (1)\n  \
+    Just Enough Items (JEI)\n\
+    disallowed Just Enough Items (JEI) doesn't allow third-party distribution\n"
+  );
+
+  let output = fixture
+    .run(&["-s", "name", "-f", "{NAME}: {LICENSE} {LICENSE_URL}\n"])
+    .await;
+
+  assert!(output.status.success());
+  assert_eq!(
+    stdout(&output),
+    "Just Enough Items (JEI):  \n\
+    Lithium: LGPL-3.0-only https://spdx.org/licenses/LGPL-3.0-only.html\n\
+    Sodium: Polyform Shield License 1.0.0 https://polyformproject.org/licenses/shield/1.0.0/\n"
+  );
+}
+
+#[tokio::test]
+async fn notes_all_rights_reserved_licenses() {
+  let mut fixture = Fixture::empty().await;
+  let pack = fixture.dir.path().join("pack");
+  let mut projects: serde_json::Value = serde_json::from_str(
+    &std::fs::read_to_string(fixtures().join("responses").join("modrinth_projects.json")).unwrap(),
+  )
+  .unwrap();
+
+  projects[1]["license"] = serde_json::json!({
+    "id": "LicenseRef-All-Rights-Reserved",
+    "name": "All Rights Reserved",
+    "url": null,
+  });
+
+  Mock::given(method("GET"))
+    .and(path("/modrinth/projects"))
+    .respond_with(ResponseTemplate::new(200).set_body_json(projects))
+    .mount(&fixture.server)
+    .await;
+
+  Mock::given(method("GET"))
+    .and(path("/modrinth/teams"))
+    .respond_with(response("modrinth_teams.json"))
+    .mount(&fixture.server)
+    .await;
+
+  copy_dir(&fixtures().join("pack"), &pack);
+  std::fs::remove_file(pack.join("mods").join("jei.pw.toml")).unwrap();
+  fixture.pack = pack;
+
+  let output = fixture.run(&["licenses"]).await;
+
+  // Only a note, the author may still allow it
+  assert!(output.status.success());
+  assert_eq!(
+    stdout(&output),
+    "All Rights Reserved (1)\n  \
+    Lithium\n\
+    Polyform Shield License 1.0.0 (1)\n  \
+    Sodium\n\
+    note Lithium is all rights reserved, check it allows distribution\n"
+  );
+}